@@ -3,12 +3,25 @@ export type TableColumn = {
     dataType?: string | null;
     notNull: boolean;
     pk: boolean;
+    pkOrdinal?: number | null; // position in the primary key, from 1
+    defaultValue?: string | null;
+    collation?: string | null;
+    autoIncrement?: boolean;
+    generated?: "STORED" | "VIRTUAL" | null;
+    generationExpression?: string | null;
   };
   
   export type TableInfo = {
     name: string;
     columns: TableColumn[];
     keys: TableKey[];
+    schema?: string | null; // null where the driver has no schemas
+    kind?: "TABLE" | "VIEW" | "MATERIALIZED_VIEW" | "FOREIGN_TABLE" | "VIRTUAL_TABLE" | "SHADOW_TABLE";
+    engine?: string | null; // MySQL only
+    collation?: string | null; // MySQL only
+    autoIncrement?: number | null; // MySQL only: next AUTO_INCREMENT value
+    module?: string | null; // SQLite virtual tables only
+    flags?: ("WITHOUT ROWID" | "STRICT")[]; // SQLite only
   };
   
   export type ForeignKeyEdge = {
@@ -16,20 +29,56 @@ export type TableColumn = {
     fromColumns: string[];
     toTable: string;
     toColumns: string[];
+    fromSchema?: string | null;
+    toSchema?: string | null;
+    onUpdate?: FkAction | null;
+    onDelete?: FkAction | null;
+    matchType?: string | null;
+    deferrable?: boolean | null;
+    initiallyDeferred?: boolean | null;
+  };
+
+  export type FkAction = "NO ACTION" | "RESTRICT" | "CASCADE" | "SET NULL" | "SET DEFAULT";
+
+  // Rows of an edge's from table whose parent row is missing (SQLite foreign_key_check)
+  export type ForeignKeyViolations = {
+    edge: ForeignKeyEdge;
+    rowCount: number;
+    rowids: number[]; // empty for WITHOUT ROWID tables
   };
   
   export type DbSchemaSummary = {
     tables: TableInfo[];
     foreignKeys: ForeignKeyEdge[];
+    schemas?: string[] | null; // user schemas, including empty ones
+    triggers?: TriggerInfo[] | null; // SQLite only
+  };
+
+  export type TriggerInfo = {
+    name: string;
+    table: string;
+    timing: "BEFORE" | "AFTER" | "INSTEAD OF";
+    event: "INSERT" | "UPDATE" | "DELETE";
+    columns: string[]; // UPDATE OF columns
+    when?: string | null;
+    body: string;
   };
   
   export type TableKey = {
-    keyType: "PRIMARY_KEY" | "FOREIGN_KEY" | "INDEX";
+    keyType: "PRIMARY_KEY" | "FOREIGN_KEY" | "UNIQUE" | "CHECK" | "INDEX";
     name?: string | null;
     columns: string[];
     refTable?: string | null;
     refColumns?: string[] | null;
     unique?: boolean | null;
+    refSchema?: string | null;
+    predicate?: string | null; // partial index condition
+    definition?: string | null; // CHECK constraint, or full index definition
+    onUpdate?: FkAction | null;
+    onDelete?: FkAction | null;
+    matchType?: string | null;
+    deferrable?: boolean | null;
+    initiallyDeferred?: boolean | null;
   };
   
   
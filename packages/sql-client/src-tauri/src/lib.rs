@@ -1,13 +1,37 @@
+mod network_cursor;
+mod mysql_plan;
+mod mysql_schema;
+mod open_results;
+mod pg_plan;
+mod pg_schema;
+mod pg_values;
+mod sql_split;
+mod sqlite_advisor;
+mod sqlite_cursor;
+mod sqlite_plan;
+mod sqlite_schema;
+mod transaction;
+
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 
-use rusqlite::{params, Connection, OpenFlags, Row, types::ValueRef};
-use serde::Serialize;
+use rusqlite::{params, Connection, InterruptHandle, OpenFlags, Row, types::ValueRef};
+use serde::{Deserialize, Serialize};
 use base64;
+use tauri::ipc::Channel;
+use tokio::sync::Semaphore;
 
 // Network drivers (Postgres/MySQL)
-use postgres::{Client as PgClient, SimpleQueryMessage, NoTls};
-use mysql::{prelude::Queryable, PooledConn as MyConn, Pool as MyPool, OptsBuilder as MyOptsBuilder, Value as MyValue};
+use postgres::{CancelToken as PgCancelToken, Client as PgClient, Statement as PgStatement, NoTls};
+use sql_split::{Dialect, StatementKind};
+use sqlite_cursor::SqliteCursor;
+use network_cursor::NetworkCursor;
+use open_results::{new_result_id, OpenResults};
+use transaction::{TransactionOp, TransactionStatus};
+use mysql::{prelude::Queryable, Column as MyColumn, Conn as MyConn, OptsBuilder as MyOptsBuilder, Row as MyRow, Value as MyValue};
+use mysql::consts::{ColumnFlags as MyColumnFlags, ColumnType as MyColumnType};
 
 // Frontend expects camelCase keys
 #[derive(Serialize, Clone)]
@@ -16,19 +40,170 @@ struct PlanTableInfo {
     table: String,
     access: String, // SCAN | SEARCH | UNKNOWN
     total_rows: Option<u64>,
+    total_rows_exact: bool, // Counted, rather than estimated from planner statistics
+    // MySQL EXPLAIN figures
+    access_type: Option<String>, // ALL, index, range, ref, eq_ref, const, ...
+    key: Option<String>,         // Index used
+    rows_examined: Option<u64>,  // Per scan
+    filtered_pct: Option<f64>,   // Share of examined rows the table's condition keeps
+}
+
+// A CREATE INDEX the SQLite advisor proposes for a table the query reads in full
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct IndexSuggestion {
+    table: String,
+    columns: Vec<String>, // Equality columns first, then one range column or the ORDER BY columns
+    statement: String,
+    reason: String,
+    validated: Option<bool>,              // Whether the plan used the index on a copy of the schema; None if not checked
+    plan_with_index: Option<Vec<String>>, // EXPLAIN QUERY PLAN details on that copy
+}
+
+// One operation of a query plan; `children` produce the rows it consumes
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct PlanNode {
+    operation: String,      // e.g. "Seq Scan", "Hash Join"
+    detail: Option<String>, // Readable one-line description, as listed in plan_steps
+    table: Option<String>,
+    index: Option<String>,
+    startup_cost: Option<f64>,
+    total_cost: Option<f64>,
+    estimated_rows: Option<f64>,
+    actual_rows: Option<f64>,    // ANALYZE only; per loop, like estimated_rows
+    actual_time_ms: Option<f64>, // ANALYZE only; per loop
+    loops: Option<u64>,
+    rows_removed: Option<f64>, // ANALYZE only; rows the node's filter discarded, per loop
+    blocks_hit: Option<u64>,   // BUFFERS only; shared and local blocks found in cache
+    blocks_read: Option<u64>,  // BUFFERS only; shared and local blocks read from disk
+    flags: Vec<String>,        // e.g. "COVERING INDEX", "CORRELATED", "TEMP B-TREE"
+    children: Vec<PlanNode>,
+}
+
+// Plan fields of a QueryResult as worked out by a driver's analyzer
+struct PlanAnalysis {
+    plan: Option<PlanNode>,
+    steps: Vec<String>,
+    insights: Vec<String>,
+    tables: Vec<PlanTableInfo>,
+    rows_scanned_estimate: u64,
+    rows_scanned_exact: bool,
+    index_suggestions: Option<Vec<IndexSuggestion>>,
+}
+
+impl PlanAnalysis {
+    fn apply(self, result: &mut QueryResult) {
+        result.plan = self.plan;
+        result.plan_steps = Some(self.steps);
+        result.insights = Some(self.insights);
+        result.plan_tables = if self.tables.is_empty() { None } else { Some(self.tables) };
+        result.rows_scanned_estimate = Some(self.rows_scanned_estimate);
+        result.rows_scanned_exact = Some(self.rows_scanned_exact);
+        result.index_suggestions = self.index_suggestions;
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ColumnInfo {
+    name: String,
+    declared_type: Option<String>, // type as written in the table definition
+    native_type: Option<String>,   // driver type: SQLite affinity, Postgres type name, MySQL column type
+    nullable: Option<bool>,        // None when the driver can't tell (e.g. expressions)
+    source_table: Option<String>,
+    source_column: Option<String>,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct QueryResult {
+    query_id: Option<String>, // Id accepted by cancel_query while the run is in flight
     columns: Vec<String>,
-    rows: Vec<HashMap<String, serde_json::Value>>,
+    column_info: Vec<ColumnInfo>, // Type and origin of each entry in `columns`
+    rows: EncodedRows,
+    row_encoding: RowEncoding,
     total_rows: Option<u64>,
     // Optional execution information for SELECT queries
     plan_steps: Option<Vec<String>>, // EXPLAIN QUERY PLAN detail strings
     insights: Option<Vec<String>>,   // Human-friendly summarized insights
     plan_tables: Option<Vec<PlanTableInfo>>, // Tables and access type
     rows_scanned_estimate: Option<u64>,      // Estimated rows scanned (sum of full scans)
+    rows_scanned_exact: Option<bool>,        // Whether rows_scanned_estimate comes from exact counts
+    plan: Option<PlanNode>, // Plan tree from the driver's analyzer
+    index_suggestions: Option<Vec<IndexSuggestion>>, // SQLite only
+    // Every statement of a multi-statement run, in order; the fields above then
+    // mirror the last statement that returned rows
+    result_sets: Option<Vec<StatementResult>>,
+    // Cursor left open for fetch_page/count_result/close_result; None for scripts
+    result_id: Option<String>,
+}
+
+// Outcome of one statement in a script
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct StatementResult {
+    columns: Vec<String>,
+    column_info: Vec<ColumnInfo>,
+    rows: EncodedRows,
+    row_count: u64,
+    affected_rows: Option<u64>, // Rows changed; None for statements that only read
+    elapsed_ms: u64,
+}
+
+// Shape of `rows` in a result, chosen by the caller
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum RowEncoding {
+    #[default]
+    Objects, // one { column: value } map per row; duplicate column names collapse
+    Arrays,  // one array per row, aligned to `columns`
+    Columns, // one array per column, aligned to `columns`
+}
+
+#[derive(Serialize, Clone)]
+#[serde(untagged)]
+enum EncodedRows {
+    Objects(Vec<HashMap<String, serde_json::Value>>),
+    Arrays(Vec<Vec<serde_json::Value>>),
+    Columns(Vec<Vec<serde_json::Value>>),
+}
+
+// Rows are collected as arrays aligned to `columns` and only reshaped here
+fn encode_rows(columns: &[String], rows: Vec<Vec<serde_json::Value>>, encoding: RowEncoding) -> EncodedRows {
+    match encoding {
+        RowEncoding::Objects => EncodedRows::Objects(
+            rows.into_iter()
+                .map(|values| columns.iter().cloned().zip(values).collect())
+                .collect(),
+        ),
+        RowEncoding::Arrays => EncodedRows::Arrays(rows),
+        RowEncoding::Columns => {
+            let mut cols: Vec<Vec<serde_json::Value>> = columns.iter().map(|_| Vec::with_capacity(rows.len())).collect();
+            for values in rows {
+                for (col, v) in cols.iter_mut().zip(values) {
+                    col.push(v);
+                }
+            }
+            EncodedRows::Columns(cols)
+        }
+    }
+}
+
+// A single statement becomes the whole result; a script keeps every statement
+// in `result_sets` and surfaces the last row-returning one at the top level
+fn statements_result(mut sets: Vec<StatementResult>, encoding: RowEncoding) -> QueryResult {
+    let (top, result_sets) = if sets.len() == 1 {
+        (sets.pop(), None)
+    } else {
+        (sets.iter().rev().find(|s| !s.columns.is_empty()).cloned(), Some(sets))
+    };
+    let total_rows = top.as_ref().filter(|_| result_sets.is_none()).map(|set| set.row_count);
+    let (columns, column_info, rows) = match top {
+        Some(set) => (set.columns, set.column_info, set.rows),
+        None => (vec![], vec![], encode_rows(&[], vec![], encoding)),
+    };
+    QueryResult { query_id: None, columns, column_info, rows, row_encoding: encoding, total_rows, plan_steps: None, insights: None, plan_tables: None, rows_scanned_estimate: None, rows_scanned_exact: None, plan: None, index_suggestions: None, result_sets, result_id: None }
 }
 
 #[derive(Serialize, Clone)]
@@ -38,17 +213,51 @@ struct TableColumn {
     data_type: Option<String>,
     not_null: bool,
     pk: bool,
+    pk_ordinal: Option<u32>,       // position in the primary key, from 1
+    default_value: Option<String>, // default expression, where the driver reports one
+    collation: Option<String>,     // where it differs from the driver's default, or always for MySQL
+    auto_increment: bool,
+    generated: Option<String>,             // STORED | VIRTUAL for generated columns
+    generation_expression: Option<String>, // where the driver reports it
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 struct TableKey {
-    key_type: String,                // PRIMARY_KEY | FOREIGN_KEY | INDEX
+    key_type: String,                // PRIMARY_KEY | FOREIGN_KEY | UNIQUE | CHECK | INDEX
     name: Option<String>,            // index name or None
-    columns: Vec<String>,            // key columns (for FK: from columns)
+    columns: Vec<String>,            // key columns (for FK: from columns; for INDEX: may be expressions)
     ref_table: Option<String>,       // for FK
     ref_columns: Option<Vec<String>>,// for FK target columns
     unique: Option<bool>,            // for INDEX
+    ref_schema: Option<String>,      // for FK, where the driver has schemas
+    predicate: Option<String>,       // for partial INDEX: its WHERE condition
+    definition: Option<String>,      // for CHECK: the constraint; for INDEX: its full definition
+    on_update: Option<String>,       // for FK: NO ACTION | RESTRICT | CASCADE | SET NULL | SET DEFAULT
+    on_delete: Option<String>,       // for FK, as on_update
+    match_type: Option<String>,      // for FK: SIMPLE | FULL | PARTIAL (SQLite: NONE)
+    deferrable: Option<bool>,        // for FK
+    initially_deferred: Option<bool>,// for FK
+}
+
+impl TableKey {
+    // The FOREIGN_KEY entry for an edge, as listed under its from table
+    fn foreign_key(edge: &ForeignKeyEdge, name: Option<String>) -> TableKey {
+        TableKey {
+            key_type: "FOREIGN_KEY".into(),
+            name,
+            columns: edge.from_columns.clone(),
+            ref_table: Some(edge.to_table.clone()),
+            ref_columns: Some(edge.to_columns.clone()),
+            ref_schema: edge.to_schema.clone(),
+            on_update: edge.on_update.clone(),
+            on_delete: edge.on_delete.clone(),
+            match_type: edge.match_type.clone(),
+            deferrable: edge.deferrable,
+            initially_deferred: edge.initially_deferred,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Serialize, Clone)]
@@ -57,6 +266,13 @@ struct TableInfo {
     name: String,
     columns: Vec<TableColumn>,
     keys: Vec<TableKey>,
+    schema: Option<String>, // None where the driver has no schemas
+    kind: String,           // TABLE | VIEW | MATERIALIZED_VIEW | FOREIGN_TABLE
+    engine: Option<String>,    // MySQL storage engine
+    collation: Option<String>, // MySQL default collation of the table
+    auto_increment: Option<u64>, // MySQL next AUTO_INCREMENT value
+    module: Option<String>,      // SQLite virtual table module (fts5, rtree, ...)
+    flags: Vec<String>,          // SQLite: WITHOUT ROWID, STRICT
 }
 
 #[derive(Serialize, Clone)]
@@ -66,6 +282,22 @@ struct ForeignKeyEdge {
     from_columns: Vec<String>,
     to_table: String,
     to_columns: Vec<String>,
+    from_schema: Option<String>,
+    to_schema: Option<String>,
+    on_update: Option<String>,
+    on_delete: Option<String>,
+    match_type: Option<String>,
+    deferrable: Option<bool>,
+    initially_deferred: Option<bool>, // None where the driver doesn't say
+}
+
+// Rows of an edge's from table whose parent row is missing
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ForeignKeyViolations {
+    edge: ForeignKeyEdge,
+    row_count: u64,
+    rowids: Vec<i64>, // empty for WITHOUT ROWID tables
 }
 
 #[derive(Serialize, Clone)]
@@ -73,12 +305,222 @@ struct ForeignKeyEdge {
 struct DbSchemaSummary {
     tables: Vec<TableInfo>,
     foreign_keys: Vec<ForeignKeyEdge>,
+    schemas: Option<Vec<String>>, // user schemas, including empty ones; None where the driver has no schemas
+    triggers: Option<Vec<TriggerInfo>>, // None where the driver's triggers aren't read
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TriggerInfo {
+    name: String,
+    table: String,
+    timing: String,       // BEFORE | AFTER | INSTEAD OF
+    event: String,        // INSERT | UPDATE | DELETE
+    columns: Vec<String>, // for UPDATE OF
+    when: Option<String>, // condition, without WHEN
+    body: String,         // statements between BEGIN and END
+}
+
+// Live SQLite connection kept open between commands so the page cache,
+// prepared statements and session state (ATTACH, TEMP tables) survive.
+struct SqliteSession {
+    file_path: String,
+    access: SqliteAccess,
+    // Declared before `conn` so open statements are finalized before it closes;
+    // locked only while holding `conn`
+    results: Mutex<OpenResults<SqliteCursor>>,
+    conn: Mutex<Connection>,
+    transaction: Mutex<TransactionStatus>, // Locked only while holding `conn`
+}
+
+// How a SQLite connection may touch its file; read-only unless the caller opts in
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum SqliteAccess {
+    #[default]
+    ReadOnly,
+    ReadWrite,
+    // Read-write, creating the file if it doesn't exist; keeps the value saved connections use
+    #[serde(rename = "readWriteCreate")]
+    CreateIfMissing,
+}
+
+impl SqliteAccess {
+    fn open_flags(self) -> OpenFlags {
+        // ATTACHed databases inherit these and SQLite refuses a mode= URI parameter
+        // that would widen them, so a read-only session can't write anywhere
+        match self {
+            SqliteAccess::ReadOnly => OpenFlags::SQLITE_OPEN_READ_ONLY,
+            SqliteAccess::ReadWrite => OpenFlags::SQLITE_OPEN_READ_WRITE,
+            SqliteAccess::CreateIfMissing => OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
+        }
+    }
 }
 
 #[derive(Default)]
 struct AppState {
-    // Maps connectionId -> sqlite file path
-    sqlite_files: Mutex<HashMap<String, String>>,
+    // Maps connectionId -> open sqlite session
+    sqlite_sessions: Mutex<HashMap<String, Arc<SqliteSession>>>,
+    // Maps connectionId -> open postgres/mysql session
+    network_sessions: Mutex<HashMap<String, Arc<NetworkSession>>>,
+    // Queries currently executing, by query id
+    queries: Arc<QueryRegistry>,
+    // Per-connection cap on blocking worker jobs
+    workers: WorkerLimits,
+}
+
+// Jobs a single connection may run or queue on the blocking pool at once.
+// Sessions serialize on their one connection, so extra jobs just wait for it;
+// the cap keeps one busy connection from tying up the worker threads other
+// tabs and connections need.
+const DEFAULT_CONNECTION_CONCURRENCY: usize = 4;
+
+#[derive(Default)]
+struct WorkerLimits {
+    per_connection: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl WorkerLimits {
+    fn semaphore(&self, connection_id: &str) -> Result<Arc<Semaphore>, String> {
+        let mut guard = self.per_connection.lock().map_err(|_| "state poisoned".to_string())?;
+        let sem = guard
+            .entry(connection_id.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(DEFAULT_CONNECTION_CONCURRENCY)));
+        Ok(sem.clone())
+    }
+}
+
+// Run a blocking driver call on the worker pool, holding one of the connection's permits
+async fn run_blocking<T, F>(state: &AppState, connection_id: &str, job: F) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+    T: Send + 'static,
+{
+    let permits = state.workers.semaphore(connection_id)?;
+    let _permit = permits.acquire_owned().await.map_err(|_| "worker pool closed".to_string())?;
+    tauri::async_runtime::spawn_blocking(job)
+        .await
+        .map_err(|e| format!("Worker error: {}", e))?
+}
+
+#[tauri::command]
+fn set_connection_concurrency(state: tauri::State<AppState>, connection_id: String, max_concurrent: usize) -> Result<(), String> {
+    if max_concurrent == 0 {
+        return Err("maxConcurrent must be at least 1".into());
+    }
+    let mut guard = state.workers.per_connection.lock().map_err(|_| "state poisoned".to_string())?;
+    // Jobs holding permits of the old semaphore finish normally
+    guard.insert(connection_id, Arc::new(Semaphore::new(max_concurrent)));
+    Ok(())
+}
+
+// Error returned by a run that was stopped through cancel_query
+const QUERY_CANCELLED: &str = "Query cancelled";
+
+// Driver-specific way to interrupt a statement running on another thread
+enum CancelHandle {
+    Sqlite(InterruptHandle),
+    Postgres(PgCancelToken),
+    // MySQL kills the statement by thread id from a second connection
+    Mysql { config: NetworkConfig, thread_id: u32 },
+}
+
+struct RunningQuery {
+    handle: Option<CancelHandle>, // None while the query waits for its connection
+    cancelled: Arc<AtomicBool>,
+    flow: Arc<StreamFlow>,
+}
+
+// Batches of a streamed result acknowledged by the frontend via stream_ack
+#[derive(Default)]
+struct StreamFlow {
+    acked: Mutex<u64>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct QueryRegistry {
+    next_id: AtomicU64,
+    running: Mutex<HashMap<String, RunningQuery>>,
+}
+
+// Registration of an in-flight query; unregisters itself when dropped
+struct QueryGuard {
+    registry: Arc<QueryRegistry>,
+    query_id: String,
+    cancelled: Arc<AtomicBool>,
+    flow: Arc<StreamFlow>,
+}
+
+impl QueryRegistry {
+    // Registers a query before it waits for a permit and its connection, so it
+    // can be cancelled while queued
+    fn begin(self: &Arc<Self>, query_id: Option<String>) -> Result<QueryGuard, String> {
+        let query_id = query_id.unwrap_or_else(|| format!("query-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flow = Arc::new(StreamFlow::default());
+        let mut guard = self.running.lock().map_err(|_| "state poisoned".to_string())?;
+        if guard.contains_key(&query_id) {
+            return Err(format!("Query id already running: {}", query_id));
+        }
+        guard.insert(query_id.clone(), RunningQuery { handle: None, cancelled: cancelled.clone(), flow: flow.clone() });
+        Ok(QueryGuard { registry: self.clone(), query_id, cancelled, flow })
+    }
+}
+
+impl QueryGuard {
+    // Makes the query interruptible once it holds its connection; a query
+    // cancelled while it waited stops here instead
+    fn attach(&self, handle: CancelHandle) -> Result<(), String> {
+        let mut guard = self.registry.running.lock().map_err(|_| "state poisoned".to_string())?;
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Err(QUERY_CANCELLED.to_string());
+        }
+        if let Some(running) = guard.get_mut(&self.query_id) {
+            running.handle = Some(handle);
+        }
+        Ok(())
+    }
+
+    // Turn the driver's error into QUERY_CANCELLED when the run was cancelled
+    fn check<T>(&self, result: Result<T, String>) -> Result<T, String> {
+        match result {
+            Err(_) if self.cancelled.load(Ordering::SeqCst) => Err(QUERY_CANCELLED.to_string()),
+            other => other,
+        }
+    }
+
+    // Attach the query id to the result
+    fn finish(self, result: Result<QueryResult, String>) -> Result<QueryResult, String> {
+        let mut r = self.check(result)?;
+        r.query_id = Some(self.query_id.clone());
+        Ok(r)
+    }
+}
+
+impl Drop for QueryGuard {
+    fn drop(&mut self) {
+        if let Ok(mut guard) = self.registry.running.lock() {
+            guard.remove(&self.query_id);
+        }
+    }
+}
+
+const SQLITE_STATEMENT_CACHE: usize = 64;
+
+fn open_sqlite_session(file_path: &str, access: SqliteAccess) -> Result<SqliteSession, String> {
+    let conn = Connection::open_with_flags(file_path, access.open_flags())
+        .map_err(|e| format!("Failed to open SQLite file: {}", e))?;
+    // A simple pragma to ensure it's a valid DB
+    conn.pragma_query(None, "schema_version", |_| Ok(()))
+        .map_err(|e| format!("Not a valid SQLite database: {}", e))?;
+    conn.set_prepared_statement_cache_capacity(SQLITE_STATEMENT_CACHE);
+    Ok(SqliteSession { file_path: file_path.to_string(), access, results: Mutex::new(OpenResults::default()), conn: Mutex::new(conn), transaction: Mutex::new(TransactionStatus::default()) })
+}
+
+fn sqlite_session(state: &AppState, connection_id: &str) -> Result<Arc<SqliteSession>, String> {
+    let guard = state.sqlite_sessions.lock().map_err(|_| "state poisoned".to_string())?;
+    guard.get(connection_id).cloned().ok_or_else(|| "No SQLite file registered for this connection".to_string())
 }
 
 fn value_ref_to_json(value: ValueRef<'_>) -> serde_json::Value {
@@ -91,685 +533,1282 @@ fn value_ref_to_json(value: ValueRef<'_>) -> serde_json::Value {
     }
 }
 
-fn row_to_map(row: &Row<'_>, column_names: &[String]) -> rusqlite::Result<HashMap<String, serde_json::Value>> {
-    let mut map = HashMap::with_capacity(column_names.len());
-    for (idx, col) in column_names.iter().enumerate() {
-        let v = row.get_ref(idx)?;
-        map.insert(col.clone(), value_ref_to_json(v));
+fn row_to_values(row: &Row<'_>, column_count: usize) -> rusqlite::Result<Vec<serde_json::Value>> {
+    let mut values = Vec::with_capacity(column_count);
+    for idx in 0..column_count {
+        values.push(value_ref_to_json(row.get_ref(idx)?));
+    }
+    Ok(values)
+}
+
+fn mysql_value_to_json(value: Option<&MyValue>) -> serde_json::Value {
+    match value {
+        Some(MyValue::NULL) | None => serde_json::Value::Null,
+        Some(MyValue::Bytes(b)) => serde_json::Value::String(String::from_utf8_lossy(b).to_string()),
+        Some(MyValue::Int(i)) => serde_json::json!(i),
+        Some(MyValue::UInt(u)) => serde_json::json!(u),
+        Some(MyValue::Float(f)) => serde_json::json!(f),
+        Some(MyValue::Double(d)) => serde_json::json!(d),
+        Some(MyValue::Date(y,m,d,h,mi,s,us)) => serde_json::json!(format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}", y,m,d,h,mi,s,us)),
+        Some(MyValue::Time(is_neg, d, h, m, s, us)) => {
+            let total_hours: u32 = (*h as u32) + d * 24;
+            serde_json::json!(format!("{}{:02}:{:02}:{:02}.{:06}", if *is_neg {"-"} else {""}, total_hours, m, s, us))
+        }
+    }
+}
+
+fn mysql_row_to_values(row: &MyRow) -> Vec<serde_json::Value> {
+    (0..row.len()).map(|idx| mysql_value_to_json(row.as_ref(idx))).collect()
+}
+
+// Type affinity SQLite derives from a declared column type
+fn sqlite_affinity(declared: &str) -> &'static str {
+    let upper = declared.to_uppercase();
+    if upper.contains("INT") { "INTEGER" }
+    else if upper.contains("CHAR") || upper.contains("CLOB") || upper.contains("TEXT") { "TEXT" }
+    else if upper.contains("BLOB") || upper.is_empty() { "BLOB" }
+    else if upper.contains("REAL") || upper.contains("FLOA") || upper.contains("DOUB") { "REAL" }
+    else { "NUMERIC" }
+}
+
+// Declared type, origin and NOT NULL of each result column of the first statement in `sql`.
+// The origin APIs need SQLITE_ENABLE_COLUMN_METADATA, which the bundled build enables.
+fn sqlite_column_info(conn: &Connection, sql: &str) -> Vec<ColumnInfo> {
+    use rusqlite::ffi;
+    use std::ffi::{c_char, c_int, CStr, CString};
+    use std::ptr::null_mut;
+
+    let Ok(c_sql) = CString::new(sql) else { return Vec::new() };
+    let text = |p: *const c_char| -> Option<String> {
+        // SAFETY: sqlite returns NUL-terminated strings valid until the statement is finalized
+        if p.is_null() { None } else { Some(unsafe { CStr::from_ptr(p) }.to_string_lossy().into_owned()) }
+    };
+    let mut columns = Vec::new();
+    // SAFETY: the caller holds the connection lock; the statement is finalized before returning
+    unsafe {
+        let db = conn.handle();
+        let mut stmt: *mut ffi::sqlite3_stmt = null_mut();
+        let rc = ffi::sqlite3_prepare_v2(db, c_sql.as_ptr(), -1, &mut stmt, std::ptr::null_mut());
+        if rc != ffi::SQLITE_OK || stmt.is_null() {
+            ffi::sqlite3_finalize(stmt);
+            return columns;
+        }
+        for i in 0..ffi::sqlite3_column_count(stmt) {
+            let db_name = ffi::sqlite3_column_database_name(stmt, i);
+            let table = ffi::sqlite3_column_table_name(stmt, i);
+            let origin = ffi::sqlite3_column_origin_name(stmt, i);
+            let declared_type = text(ffi::sqlite3_column_decltype(stmt, i));
+            let mut nullable = None;
+            if !table.is_null() && !origin.is_null() {
+                let (mut not_null, mut pk): (c_int, c_int) = (0, 0);
+                let rc = ffi::sqlite3_table_column_metadata(db, db_name, table, origin, null_mut(), null_mut(), &mut not_null, &mut pk, null_mut());
+                // An INTEGER PRIMARY KEY aliases the rowid and can never be NULL
+                let rowid_alias = pk != 0 && declared_type.as_deref().is_some_and(|t| t.eq_ignore_ascii_case("INTEGER"));
+                if rc == ffi::SQLITE_OK { nullable = Some(not_null == 0 && !rowid_alias); }
+            }
+            columns.push(ColumnInfo {
+                name: text(ffi::sqlite3_column_name(stmt, i)).unwrap_or_default(),
+                native_type: declared_type.as_deref().map(|t| sqlite_affinity(t).to_string()),
+                declared_type,
+                nullable,
+                source_table: text(table),
+                source_column: text(origin),
+            });
+        }
+        ffi::sqlite3_finalize(stmt);
+    }
+    columns
+}
+
+// Result column types from the prepared statement, with declared type and
+// NOT NULL looked up in pg_attribute for columns that come straight from a table
+fn pg_column_info(client: &mut PgClient, stmt: &PgStatement) -> Vec<ColumnInfo> {
+    let mut table_oids: Vec<u32> = stmt.columns().iter().filter_map(|c| c.table_oid()).collect();
+    table_oids.sort_unstable();
+    table_oids.dedup();
+    // (table oid, attnum) -> (table, column, declared type, not null)
+    let mut attrs: HashMap<(u32, i16), (String, String, String, bool)> = HashMap::new();
+    if !table_oids.is_empty() {
+        let rows = client.query(
+            "SELECT a.attrelid, a.attnum, c.relname, a.attname, format_type(a.atttypid, a.atttypmod), a.attnotnull \
+             FROM pg_attribute a JOIN pg_class c ON c.oid = a.attrelid \
+             WHERE a.attrelid = ANY($1) AND a.attnum > 0 AND NOT a.attisdropped",
+            &[&table_oids],
+        );
+        for row in rows.unwrap_or_default() {
+            attrs.insert((row.get(0), row.get(1)), (row.get(2), row.get(3), row.get(4), row.get(5)));
+        }
+    }
+    stmt.columns()
+        .iter()
+        .map(|c| {
+            let attr = c.table_oid().zip(c.column_id()).and_then(|key| attrs.get(&key));
+            ColumnInfo {
+                name: c.name().to_string(),
+                declared_type: attr.map(|a| a.2.clone()),
+                native_type: Some(c.type_().name().to_string()),
+                nullable: attr.map(|a| !a.3),
+                source_table: attr.map(|a| a.0.clone()),
+                source_column: attr.map(|a| a.1.clone()),
+            }
+        })
+        .collect()
+}
+
+fn mysql_type_name(col: &MyColumn) -> String {
+    let flags = col.flags();
+    let name = match col.column_type() {
+        _ if flags.contains(MyColumnFlags::ENUM_FLAG) => "ENUM",
+        _ if flags.contains(MyColumnFlags::SET_FLAG) => "SET",
+        MyColumnType::MYSQL_TYPE_TINY => "TINYINT",
+        MyColumnType::MYSQL_TYPE_SHORT => "SMALLINT",
+        MyColumnType::MYSQL_TYPE_INT24 => "MEDIUMINT",
+        MyColumnType::MYSQL_TYPE_LONG => "INT",
+        MyColumnType::MYSQL_TYPE_LONGLONG => "BIGINT",
+        MyColumnType::MYSQL_TYPE_DECIMAL | MyColumnType::MYSQL_TYPE_NEWDECIMAL => "DECIMAL",
+        MyColumnType::MYSQL_TYPE_FLOAT => "FLOAT",
+        MyColumnType::MYSQL_TYPE_DOUBLE => "DOUBLE",
+        MyColumnType::MYSQL_TYPE_BIT => "BIT",
+        MyColumnType::MYSQL_TYPE_DATE | MyColumnType::MYSQL_TYPE_NEWDATE => "DATE",
+        MyColumnType::MYSQL_TYPE_TIME | MyColumnType::MYSQL_TYPE_TIME2 => "TIME",
+        MyColumnType::MYSQL_TYPE_DATETIME | MyColumnType::MYSQL_TYPE_DATETIME2 => "DATETIME",
+        MyColumnType::MYSQL_TYPE_TIMESTAMP | MyColumnType::MYSQL_TYPE_TIMESTAMP2 => "TIMESTAMP",
+        MyColumnType::MYSQL_TYPE_YEAR => "YEAR",
+        MyColumnType::MYSQL_TYPE_JSON => "JSON",
+        MyColumnType::MYSQL_TYPE_ENUM => "ENUM",
+        MyColumnType::MYSQL_TYPE_SET => "SET",
+        MyColumnType::MYSQL_TYPE_GEOMETRY => "GEOMETRY",
+        MyColumnType::MYSQL_TYPE_NULL => "NULL",
+        // charset 63 is `binary`: BLOB/VARBINARY/BINARY rather than TEXT/VARCHAR/CHAR
+        MyColumnType::MYSQL_TYPE_TINY_BLOB
+        | MyColumnType::MYSQL_TYPE_MEDIUM_BLOB
+        | MyColumnType::MYSQL_TYPE_LONG_BLOB
+        | MyColumnType::MYSQL_TYPE_BLOB => if col.character_set() == 63 { "BLOB" } else { "TEXT" },
+        MyColumnType::MYSQL_TYPE_VARCHAR | MyColumnType::MYSQL_TYPE_VAR_STRING => if col.character_set() == 63 { "VARBINARY" } else { "VARCHAR" },
+        MyColumnType::MYSQL_TYPE_STRING => if col.character_set() == 63 { "BINARY" } else { "CHAR" },
+        _ => "UNKNOWN",
+    };
+    if flags.contains(MyColumnFlags::UNSIGNED_FLAG) && col.column_type().is_numeric_type() {
+        format!("{} UNSIGNED", name)
+    } else {
+        name.to_string()
     }
-    Ok(map)
+}
+
+fn mysql_column_info(columns: &[MyColumn]) -> Vec<ColumnInfo> {
+    let non_empty = |s: std::borrow::Cow<'_, str>| if s.is_empty() { None } else { Some(s.into_owned()) };
+    columns
+        .iter()
+        .map(|c| ColumnInfo {
+            name: c.name_str().to_string(),
+            declared_type: None,
+            native_type: Some(mysql_type_name(c)),
+            nullable: Some(!c.flags().contains(MyColumnFlags::NOT_NULL_FLAG)),
+            source_table: non_empty(c.org_table_str()),
+            source_column: non_empty(c.org_name_str()),
+        })
+        .collect()
 }
 
 #[tauri::command]
-fn sqlite_open(state: tauri::State<AppState>, connection_id: String, file_path: String) -> Result<(), String> {
+fn sqlite_open(state: tauri::State<AppState>, connection_id: String, file_path: String, access: Option<SqliteAccess>) -> Result<(), String> {
     if file_path.is_empty() {
         return Err("filePath is required".into());
     }
-    // Validate file can be opened
-    let conn = Connection::open_with_flags(&file_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
-        .map_err(|e| format!("Failed to open SQLite file: {}", e))?;
-    // A simple pragma to ensure it's a valid DB
-    conn.pragma_query(None, "schema_version", |_| Ok(()))
-        .map_err(|e| format!("Not a valid SQLite database: {}", e))?;
-    drop(conn);
+    let access = access.unwrap_or_default();
+    // Opening the same file again keeps the live session (and its TEMP/ATTACH state)
+    {
+        let guard = state.sqlite_sessions.lock().map_err(|_| "state poisoned".to_string())?;
+        if guard.get(&connection_id).is_some_and(|s| s.file_path == file_path && s.access == access) {
+            return Ok(());
+        }
+    }
+    let session = open_sqlite_session(&file_path, access)?;
 
-    let mut guard = state.sqlite_files.lock().map_err(|_| "state poisoned".to_string())?;
-    guard.insert(connection_id, file_path);
+    let mut guard = state.sqlite_sessions.lock().map_err(|_| "state poisoned".to_string())?;
+    guard.insert(connection_id, Arc::new(session));
     Ok(())
 }
 
 #[tauri::command]
-fn run_sqlite_query(
-    state: tauri::State<AppState>,
+fn sqlite_close(state: tauri::State<AppState>, connection_id: String) -> Result<bool, String> {
+    let session = {
+        let mut guard = state.sqlite_sessions.lock().map_err(|_| "state poisoned".to_string())?;
+        guard.remove(&connection_id)
+    };
+    // Returns whether an open transaction was rolled back. A busy connection closes
+    // once the last in-flight command releases it, and closing rolls back as well
+    let Some(session) = session else { return Ok(false) };
+    let Ok(conn) = session.conn.try_lock() else { return Ok(false) };
+    if conn.is_autocommit() {
+        return Ok(false);
+    }
+    conn.execute_batch("ROLLBACK").map_err(|e| format!("Rollback error: {}", e))?;
+    Ok(true)
+}
+
+#[tauri::command]
+fn sqlite_reopen(state: tauri::State<AppState>, connection_id: String) -> Result<(), String> {
+    let current = sqlite_session(&state, &connection_id)?;
+    let session = open_sqlite_session(&current.file_path, current.access)?;
+    let mut guard = state.sqlite_sessions.lock().map_err(|_| "state poisoned".to_string())?;
+    guard.insert(connection_id, Arc::new(session));
+    Ok(())
+}
+
+#[tauri::command]
+async fn run_sqlite_query(
+    state: tauri::State<'_, AppState>,
     connection_id: String,
     sql: String,
-    page: u32,
-    page_size: u32,
+    query_id: Option<String>,
+    options: Option<RunOptions>,
 ) -> Result<QueryResult, String> {
-    let file_path = {
-        let guard = state.sqlite_files.lock().map_err(|_| "state poisoned".to_string())?;
-        guard.get(&connection_id).cloned().ok_or_else(|| "No SQLite file registered for this connection".to_string())?
-    };
-
-    let conn = Connection::open_with_flags(file_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
-        .map_err(|e| format!("Failed to open DB: {}", e))?;
+    let session = sqlite_session(&state, &connection_id)?;
+    let options = options.unwrap_or_default();
+    let running = state.queries.begin(query_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.attach(CancelHandle::Sqlite(conn.get_interrupt_handle()))?;
+        let mut tx = session.transaction.lock().map_err(|_| "connection poisoned".to_string())?;
+        let mut results = session.results.lock().map_err(|_| "connection poisoned".to_string())?;
+        let exact_counts = options.exact_counts.unwrap_or(false);
+        running.finish(sqlite_query(&conn, &sql, options.paging(), options.encoding.unwrap_or_default(), exact_counts, &mut tx, &mut results))
+    })
+    .await
+}
 
-    // Normalize SQL: remove trailing semicolons and whitespace
-    let sql_clean: String = sql.trim().trim_end_matches(';').trim().to_string();
-    let trimmed = sql_clean.trim_start();
-    let is_select = trimmed.to_lowercase().starts_with("select");
+// How run_sqlite_query and run_network_query page and shape their result
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct RunOptions {
+    page: Option<u32>,
+    page_size: Option<u32>, // Without it every row is returned and nothing is kept open
+    encoding: Option<RowEncoding>,
+    count_total: Option<bool>,  // false leaves total_rows to count_result
+    exact_counts: Option<bool>, // SQLite: plan table sizes by COUNT(*) rather than from statistics
+    analyze: Option<bool>,      // Network: EXPLAIN ANALYZE runs the query again, rolled back (MySQL 8.0.18+)
+}
 
-    if !is_select {
-        // Non-select: execute and return empty result
-        conn.execute_batch(&sql_clean)
-            .map_err(|e| format!("Execution error: {}", e))?;
-        return Ok(QueryResult { columns: vec![], rows: vec![], total_rows: None, plan_steps: None, insights: None, plan_tables: None, rows_scanned_estimate: None });
+impl RunOptions {
+    fn paging(&self) -> Option<Paging> {
+        self.page_size.map(|page_size| Paging { page: self.page.unwrap_or(0), page_size, count_total: self.count_total.unwrap_or(true) })
     }
+}
 
-    // Total rows
-    let count_sql = format!("SELECT COUNT(*) AS count FROM ( {} )", sql_clean);
-    let total_rows: u64 = conn
-        .query_row(&count_sql, [], |r| r.get::<_, i64>(0))
-        .map(|v| v as u64)
-        .map_err(|e| format!("Count error: {}", e))?;
-
-    // Paged rows
-    let paged_sql = format!("SELECT * FROM ( {} ) LIMIT ? OFFSET ?", sql_clean);
-    let mut stmt = conn
-        .prepare(&paged_sql)
-        .map_err(|e| format!("Prepare error: {}", e))?;
+// Which page of a result a run returns, and whether it counts every row
+struct Paging {
+    page: u32,
+    page_size: u32,
+    count_total: bool,
+}
 
-    let col_names: Vec<String> = stmt
-        .column_names()
-        .into_iter()
-        .map(|s| s.to_string())
-        .collect();
+// Runs a query or script. With paging a lone query is left open as a cursor and
+// only the requested page is read; without, every row comes back at once. A lone
+// query gets its plan analyzed either way
+fn sqlite_query(
+    conn: &Connection,
+    sql: &str,
+    paging: Option<Paging>,
+    encoding: RowEncoding,
+    exact_counts: bool,
+    tx: &mut TransactionStatus,
+    results: &mut OpenResults<SqliteCursor>,
+) -> Result<QueryResult, String> {
+    // Only a lone plain query can be kept open and counted; anything else runs
+    // statement by statement, keeping whatever rows each returns
+    let statements = sql_split::split(sql, Dialect::Sqlite);
+    let sql_clean = match statements.as_slice() {
+        [only] if only.kind == StatementKind::Query => only.sql.as_str(),
+        _ => {
+            // An open cursor's statement keeps its tables locked against DROP, ALTER
+            // and the like, and holds the file's shared lock against writers
+            results.clear();
+            return Ok(statements_result(sqlite_script(conn, sql, encoding, tx)?, encoding));
+        }
+    };
 
-    let offset: u32 = page.saturating_mul(page_size);
-    let rows_iter = stmt
-        .query_map(params![page_size as i64, offset as i64], |row| row_to_map(row, &col_names))
-        .map_err(|e| format!("Query error: {}", e))?;
-
-    let mut rows: Vec<HashMap<String, serde_json::Value>> = Vec::new();
-    for item in rows_iter {
-        rows.push(item.map_err(|e| format!("Row error: {}", e))?);
-    }
-
-    // Total rows (for SELECT queries)
-    let count_sql = format!("SELECT COUNT(*) AS count FROM ( {} )", sql_clean);
-    let total_rows: u64 = conn
-        .query_row(&count_sql, [], |r| r.get::<_, i64>(0))
-        .map(|v| v as u64)
-        .map_err(|e| format!("Count error: {}", e))?;
-
-    // Explain query plan for insights
-    let mut plan_steps: Vec<String> = Vec::new();
-    let mut explain_stmt = conn
-        .prepare(&format!("EXPLAIN QUERY PLAN {}", sql_clean))
-        .map_err(|e| format!("Explain prepare error: {}", e))?;
-    let explain_iter = explain_stmt
-        .query_map([], |row| {
-            // columns: id, parent, notused, detail
-            let detail: String = row.get(3)?;
-            Ok(detail)
-        })
-        .map_err(|e| format!("Explain query error: {}", e))?;
-    for s in explain_iter {
-        plan_steps.push(s.map_err(|e| format!("Explain row error: {}", e))?);
-    }
-
-    // Derive simple insights from plan
-    let mut insights: Vec<String> = Vec::new();
-    let mut table_access: std::collections::HashMap<String, String> = std::collections::HashMap::new();
-    for step in &plan_steps {
-        let upper = step.to_uppercase();
-        if upper.contains("USING INDEX") {
-            insights.push(format!("Index used: {}", step));
-        }
-        if upper.contains("SCAN ") && !upper.contains("SEARCH ") {
-            insights.push(format!("Full scan: {}", step));
-        }
-
-        // Extract table name if present
-        // Patterns observed:
-        //   "SCAN TABLE <name>"
-        //   "SEARCH <name> USING ..." (sometimes without TABLE)
-        //   "SEARCH TABLE <name> USING ..."
-        let access = if upper.contains("SEARCH ") { "SEARCH" } else if upper.contains("SCAN ") { "SCAN" } else { "UNKNOWN" };
-        let mut pos = None;
-        if let Some(i) = upper.find("SEARCH ") { pos = Some(i + 7); }
-        if let Some(i) = upper.find("SCAN ") { pos = Some(i + 5); }
-        if let Some(mut start) = pos {
-            // skip whitespace
-            let chars: Vec<char> = step.chars().collect();
-            while start < chars.len() && chars[start].is_whitespace() { start += 1; }
-            // Optional TABLE keyword (case-insensitive)
-            let tail_upper = step[start..].to_uppercase();
-            if tail_upper.starts_with("TABLE ") {
-                start += 6; // len("TABLE ")
-            }
-            // Capture identifier or quoted name
-            let mut name = String::new();
-            if start < chars.len() && (chars[start] == '"' || chars[start] == '\'') {
-                let quote = chars[start];
-                start += 1;
-                while start < chars.len() && chars[start] != quote { name.push(chars[start]); start += 1; }
-            } else {
-                while start < chars.len() {
-                    let ch = chars[start];
-                    if ch.is_alphanumeric() || ch == '_' { name.push(ch); start += 1; }
-                    else { break; }
-                }
-            }
-            if !name.is_empty() {
-                // Prefer SCAN over SEARCH as a stronger signal
-                let existing = table_access.get(&name).cloned();
-                match existing.as_deref() {
-                    Some("SCAN") => { /* keep SCAN */ }
-                    _ => { table_access.insert(name, access.to_string()); }
-                }
+    let (mut result, opened) = match paging {
+        Some(paging) => {
+            // The query runs once; later pages are read from the same cursor by fetch_page
+            let mut cursor = SqliteCursor::open(conn, sql_clean)?;
+            tx.observe(sql_clean, false, Dialect::Sqlite);
+            let rows = cursor.page(conn, paging.page, paging.page_size)?;
+            // Counting runs the query a second time unless the page already read every row
+            if paging.count_total {
+                cursor.count(conn)?;
             }
+            let result_id = new_result_id();
+            (page_result(&cursor.columns, &cursor.column_info, rows, cursor.total_rows, encoding, &result_id), Some((result_id, cursor)))
         }
+        None => (statements_result(sqlite_script(conn, sql_clean, encoding, tx)?, encoding), None),
+    };
+    // The query itself succeeded; a plan that can't be had is only reported
+    match sqlite_plan::analyze(conn, sql_clean, exact_counts) {
+        Ok(analysis) => analysis.apply(&mut result),
+        Err(e) => result.insights = Some(vec![format!("Plan unavailable: {}", e)]),
     }
-    if insights.is_empty() && !plan_steps.is_empty() {
-        insights.push("Plan analyzed with no obvious full scans".to_string());
+    if let Some((result_id, cursor)) = opened {
+        // An evicted cursor is finalized as it drops
+        results.insert(result_id, cursor);
     }
+    Ok(result)
+}
 
-    // For tables referenced, collect total row counts and estimate scanned rows for full scans
-    let mut plan_tables: Vec<PlanTableInfo> = Vec::new();
-    let mut rows_scanned_estimate: u64 = 0;
-    for (table, access) in table_access.into_iter() {
-        // Skip SQLite internal tables
-        if table.starts_with("sqlite_") { continue; }
-        let total: Option<u64> = match conn.query_row(&format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "")), [], |r| r.get::<_, i64>(0)) {
-            Ok(v) => Some(v as u64),
-            Err(_) => None,
-        };
-        if access == "SCAN" {
-            if let Some(t) = total { rows_scanned_estimate = rows_scanned_estimate.saturating_add(t); }
-        }
-        plan_tables.push(PlanTableInfo { table, access, total_rows: total });
-    }
-    // Always return a numeric estimate (0 if no full scans detected)
-    let rows_scanned_estimate_opt = Some(rows_scanned_estimate);
-
-    Ok(QueryResult {
-        columns: col_names,
-        rows,
-        total_rows: Some(total_rows),
-        plan_steps: Some(plan_steps),
-        insights: Some(insights),
-        plan_tables: if plan_tables.is_empty() { None } else { Some(plan_tables) },
-        rows_scanned_estimate: rows_scanned_estimate_opt,
+// One page of an open result; plan fields are only filled in by the run that opened it
+fn page_result(columns: &[String], column_info: &[ColumnInfo], rows: Vec<Vec<serde_json::Value>>, total_rows: Option<u64>, encoding: RowEncoding, result_id: &str) -> QueryResult {
+    QueryResult {
+        query_id: None,
+        rows: encode_rows(columns, rows, encoding),
+        row_encoding: encoding,
+        column_info: column_info.to_vec(),
+        columns: columns.to_vec(),
+        total_rows,
+        plan_steps: None,
+        insights: None,
+        plan_tables: None,
+        rows_scanned_estimate: None,
+        rows_scanned_exact: None,
+        plan: None,
+        index_suggestions: None,
+        result_sets: None,
+        result_id: Some(result_id.to_string()),
+    }
+}
+
+// Another page of a result left open by run_sqlite_query or run_network_query
+#[tauri::command]
+async fn fetch_page(
+    state: tauri::State<'_, AppState>,
+    connection_id: String,
+    result_id: String,
+    page: u32,
+    page_size: u32,
+    query_id: Option<String>,
+    encoding: Option<RowEncoding>,
+) -> Result<QueryResult, String> {
+    let encoding = encoding.unwrap_or_default();
+    if let Ok(session) = sqlite_session(&state, &connection_id) {
+        let running = state.queries.begin(query_id)?;
+        return run_blocking(&state, &connection_id, move || {
+            let conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+            running.attach(CancelHandle::Sqlite(conn.get_interrupt_handle()))?;
+            let mut results = session.results.lock().map_err(|_| "connection poisoned".to_string())?;
+            let cursor = results.get_mut(&result_id)?;
+            let page = cursor.page(&conn, page, page_size).map(|rows| page_result(&cursor.columns, &cursor.column_info, rows, cursor.total_rows, encoding, &result_id));
+            if page.is_err() {
+                // A statement that failed (or was interrupted) can't be stepped further
+                results.close(&result_id);
+            }
+            running.finish(page)
+        })
+        .await;
+    }
+    let session = network_session(&state, &connection_id)?;
+    let running = state.queries.begin(query_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let mut client = session.client.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.attach(network_cancel_handle(&session, &client))?;
+        let mut results = session.results.lock().map_err(|_| "connection poisoned".to_string())?;
+        let cursor = results.get_mut(&result_id)?;
+        let page = cursor.page(&mut client, page, page_size).map(|rows| page_result(&cursor.columns, &cursor.column_info, rows, cursor.total_rows, encoding, &result_id));
+        if page.is_err() {
+            if let Some(cursor) = results.close(&result_id) {
+                let _ = cursor.close(&mut client); // Best effort; the page error is the one to report
+            }
+        }
+        running.finish(page)
     })
+    .await
 }
 
+// Total row count of an open result, for runs that skipped counting
 #[tauri::command]
-fn run_network_query(
+async fn count_result(state: tauri::State<'_, AppState>, connection_id: String, result_id: String, query_id: Option<String>) -> Result<u64, String> {
+    if let Ok(session) = sqlite_session(&state, &connection_id) {
+        let running = state.queries.begin(query_id)?;
+        return run_blocking(&state, &connection_id, move || {
+            let conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+            running.attach(CancelHandle::Sqlite(conn.get_interrupt_handle()))?;
+            let mut results = session.results.lock().map_err(|_| "connection poisoned".to_string())?;
+            running.check(results.get_mut(&result_id).and_then(|cursor| cursor.count(&conn)))
+        })
+        .await;
+    }
+    let session = network_session(&state, &connection_id)?;
+    let running = state.queries.begin(query_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let mut client = session.client.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.attach(network_cancel_handle(&session, &client))?;
+        let mut results = session.results.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.check(results.get_mut(&result_id).and_then(|cursor| cursor.count(&mut client)))
+    })
+    .await
+}
+
+// Releases an open result; returns whether it was still open
+#[tauri::command]
+async fn close_result(state: tauri::State<'_, AppState>, connection_id: String, result_id: String) -> Result<bool, String> {
+    if let Ok(session) = sqlite_session(&state, &connection_id) {
+        return run_blocking(&state, &connection_id, move || {
+            let _conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+            let mut results = session.results.lock().map_err(|_| "connection poisoned".to_string())?;
+            Ok(results.close(&result_id).is_some())
+        })
+        .await;
+    }
+    let session = network_session(&state, &connection_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let mut client = session.client.lock().map_err(|_| "connection poisoned".to_string())?;
+        let mut results = session.results.lock().map_err(|_| "connection poisoned".to_string())?;
+        let Some(cursor) = results.close(&result_id) else { return Ok(false) };
+        cursor.close(&mut client)?;
+        Ok(true)
+    })
+    .await
+}
+
+// Connection settings for a network session; compared on re-open so an
+// unchanged connection keeps its session.
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct NetworkConfig {
     driver: String, // "postgres" | "mysql"
     host: String,
     port: u16,
     database: String,
     user: String,
     password: Option<String>,
-    ssl: Option<bool>,
-    sql: String,
-) -> Result<QueryResult, String> {
-    // Normalize; pagination and EXPLAIN are not implemented for network drivers in this minimal pass
-    let sql_clean: String = sql.trim().trim_end_matches(';').trim().to_string();
-    let is_select: bool = sql_clean.to_lowercase().starts_with("select");
+    #[serde(default)]
+    ssl: bool,
+}
 
-    match driver.as_str() {
+enum NetworkClient {
+    Postgres(Box<PgClient>), // Boxed; it is several times the size of a MySQL Conn
+    Mysql(MyConn),
+}
+
+// Live Postgres/MySQL session kept open between commands so session settings,
+// temp tables and open transactions persist across queries.
+struct NetworkSession {
+    config: NetworkConfig,
+    client: Mutex<NetworkClient>,
+    transaction: Mutex<TransactionStatus>, // Locked only while holding `client`
+    results: Mutex<OpenResults<NetworkCursor>>, // Locked only while holding `client`
+}
+
+fn connect_network(config: &NetworkConfig) -> Result<NetworkClient, String> {
+    match config.driver.as_str() {
         "postgres" => {
             // Connection string
             // e.g. host=localhost port=5432 dbname=postgres user=postgres password=... sslmode=disable|require
             let mut params: Vec<String> = Vec::new();
-            params.push(format!("host={}", host));
-            params.push(format!("port={}", port));
-            params.push(format!("dbname={}", database));
-            params.push(format!("user={}", user));
-            if let Some(pw) = password { params.push(format!("password={}", pw)); }
-            let sslmode = if ssl.unwrap_or(false) { "require" } else { "disable" };
+            params.push(format!("host={}", config.host));
+            params.push(format!("port={}", config.port));
+            params.push(format!("dbname={}", config.database));
+            params.push(format!("user={}", config.user));
+            if let Some(pw) = &config.password { params.push(format!("password={}", pw)); }
+            let sslmode = if config.ssl { "require" } else { "disable" };
             params.push(format!("sslmode={}", sslmode));
             let conn_str = params.join(" ");
 
-            let mut client = PgClient::connect(&conn_str, NoTls)
+            let client = PgClient::connect(&conn_str, NoTls)
                 .map_err(|e| format!("Postgres connect error: {}", e))?;
-
-            // For simplicity, use simple_query which returns heterogeneous messages
-            let messages = client
-                .simple_query(&sql_clean)
-                .map_err(|e| format!("Postgres query error: {}", e))?;
-
-            // Aggregate first result set into QueryResult
-            let mut columns: Vec<String> = Vec::new();
-            let mut rows: Vec<HashMap<String, serde_json::Value>> = Vec::new();
-            for msg in messages {
-                match msg {
-                    SimpleQueryMessage::Row(row) => {
-                        if columns.is_empty() {
-                            columns = row.columns().iter().map(|c| c.name().to_string()).collect();
-                        }
-                        let mut obj = HashMap::with_capacity(row.len());
-                        for (i, col) in columns.iter().enumerate() {
-                            let v = row.get(i);
-                            obj.insert(col.clone(), match v {
-                                Some(s) => serde_json::Value::String(s.to_string()),
-                                None => serde_json::Value::Null,
-                            });
-                        }
-                        rows.push(obj);
-                    }
-                    SimpleQueryMessage::CommandComplete(_c) => { /* ignore */ }
-                    _ => {}
-                }
-            }
-            Ok(QueryResult { columns, rows, total_rows: None, plan_steps: None, insights: None, plan_tables: None, rows_scanned_estimate: None })
+            Ok(NetworkClient::Postgres(Box::new(client)))
         }
         "mysql" => {
             let mut opts = MyOptsBuilder::new();
-            opts = opts.ip_or_hostname(Some(host));
-            opts = opts.tcp_port(port);
-            opts = opts.db_name(Some(database));
-            opts = opts.user(Some(user));
-            if let Some(pw) = password { opts = opts.pass(Some(pw)); }
-            let pool = MyPool::new(opts).map_err(|e| format!("MySQL connect error: {}", e))?;
-            let mut conn: MyConn = pool.get_conn().map_err(|e| format!("MySQL get_conn error: {}", e))?;
-
-            if !is_select {
-                conn.exec_drop(sql_clean, ()).map_err(|e| format!("MySQL exec error: {}", e))?;
-                return Ok(QueryResult { columns: vec![], rows: vec![], total_rows: None, plan_steps: None, insights: None, plan_tables: None, rows_scanned_estimate: None });
+            opts = opts.ip_or_hostname(Some(config.host.clone()));
+            opts = opts.tcp_port(config.port);
+            opts = opts.db_name(Some(config.database.clone()));
+            opts = opts.user(Some(config.user.clone()));
+            if let Some(pw) = &config.password { opts = opts.pass(Some(pw.clone())); }
+            let conn = MyConn::new(opts).map_err(|e| format!("MySQL connect error: {}", e))?;
+            Ok(NetworkClient::Mysql(conn))
+        }
+        _ => Err("Unsupported driver".into())
+    }
+}
+
+fn network_session(state: &AppState, connection_id: &str) -> Result<Arc<NetworkSession>, String> {
+    let guard = state.network_sessions.lock().map_err(|_| "state poisoned".to_string())?;
+    guard.get(connection_id).cloned().ok_or_else(|| "No network session open for this connection".to_string())
+}
+
+#[tauri::command]
+async fn network_open(state: tauri::State<'_, AppState>, connection_id: String, config: NetworkConfig) -> Result<(), String> {
+    // Re-opening with identical settings keeps the live session
+    {
+        let guard = state.network_sessions.lock().map_err(|_| "state poisoned".to_string())?;
+        if guard.get(&connection_id).is_some_and(|s| s.config == config) {
+            return Ok(());
+        }
+    }
+    // Connecting can hang on an unreachable host; keep it off the invoke thread
+    let connect_config = config.clone();
+    let client = run_blocking(&state, &connection_id, move || connect_network(&connect_config)).await?;
+    let session = NetworkSession { config, client: Mutex::new(client), transaction: Mutex::new(TransactionStatus::default()), results: Mutex::new(OpenResults::default()) };
+
+    let mut guard = state.network_sessions.lock().map_err(|_| "state poisoned".to_string())?;
+    guard.insert(connection_id, Arc::new(session));
+    Ok(())
+}
+
+#[tauri::command]
+fn network_close(state: tauri::State<AppState>, connection_id: String) -> Result<bool, String> {
+    let session = {
+        let mut guard = state.network_sessions.lock().map_err(|_| "state poisoned".to_string())?;
+        guard.remove(&connection_id)
+    };
+    // Returns whether an open transaction was rolled back. A busy client disconnects
+    // once the last in-flight command releases it, and the server rolls back then
+    let Some(session) = session else { return Ok(false) };
+    let Ok(mut client) = session.client.try_lock() else { return Ok(false) };
+    let Ok(tx) = session.transaction.try_lock() else { return Ok(false) };
+    if !tx.active {
+        return Ok(false);
+    }
+    match &mut *client {
+        NetworkClient::Postgres(c) => c.batch_execute("ROLLBACK").map_err(|e| format!("Postgres query error: {}", e))?,
+        NetworkClient::Mysql(c) => c.query_drop("ROLLBACK").map_err(|e| format!("MySQL query error: {}", e))?,
+    }
+    Ok(true)
+}
+
+#[tauri::command]
+async fn run_network_query(
+    state: tauri::State<'_, AppState>,
+    connection_id: String,
+    sql: String,
+    query_id: Option<String>,
+    options: Option<RunOptions>,
+) -> Result<QueryResult, String> {
+    let session = network_session(&state, &connection_id)?;
+    let options = options.unwrap_or_default();
+    let running = state.queries.begin(query_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let mut client = session.client.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.attach(network_cancel_handle(&session, &client))?;
+        let mut tx = session.transaction.lock().map_err(|_| "connection poisoned".to_string())?;
+        let mut results = session.results.lock().map_err(|_| "connection poisoned".to_string())?;
+        let encoding = options.encoding.unwrap_or_default();
+        running.finish(network_run(&mut client, &sql, options.paging(), options.analyze.unwrap_or(false), encoding, &mut tx, &mut results))
+    })
+    .await
+}
+
+fn network_cancel_handle(session: &NetworkSession, client: &NetworkClient) -> CancelHandle {
+    match client {
+        NetworkClient::Postgres(c) => CancelHandle::Postgres(c.cancel_token()),
+        NetworkClient::Mysql(c) => CancelHandle::Mysql { config: session.config.clone(), thread_id: c.connection_id() },
+    }
+}
+
+// The network side of sqlite_query: paged through a cursor or read whole, with
+// the plan of a lone query analyzed either way
+fn network_run(
+    client: &mut NetworkClient,
+    sql: &str,
+    paging: Option<Paging>,
+    analyze: bool,
+    encoding: RowEncoding,
+    tx: &mut TransactionStatus,
+    results: &mut OpenResults<NetworkCursor>,
+) -> Result<QueryResult, String> {
+    let dialect = match client {
+        NetworkClient::Postgres(_) => Dialect::Postgres,
+        NetworkClient::Mysql(_) => Dialect::Mysql,
+    };
+    // As with SQLite, only a lone plain query is kept open; scripts return every row
+    let statements = sql_split::split(sql, dialect);
+    let sql_clean = match statements.as_slice() {
+        [only] if only.kind == StatementKind::Query => only.sql.as_str(),
+        _ => return network_query(client, sql, encoding, tx),
+    };
+
+    let (mut result, opened) = match paging {
+        Some(paging) if network_cursor::can_open(client, sql_clean) => {
+            let result_id = new_result_id();
+            let mut cursor = NetworkCursor::open(client, sql_clean, &result_id, tx.active)?;
+            tx.observe(sql_clean, false, dialect);
+            let rows = cursor.page(client, paging.page, paging.page_size)?;
+            if paging.count_total {
+                cursor.count(client)?;
             }
+            (page_result(&cursor.columns, &cursor.column_info, rows, cursor.total_rows, encoding, &result_id), Some((result_id, cursor)))
+        }
+        _ => (network_query(client, sql_clean, encoding, tx)?, None),
+    };
+    let analysis = match client {
+        NetworkClient::Postgres(client) => pg_plan::analyze(client, sql_clean, analyze, tx.active),
+        NetworkClient::Mysql(conn) => mysql_plan::analyze(conn, sql_clean, analyze, tx.active),
+    };
+    if let Some((result_id, cursor)) = opened {
+        if let Some(evicted) = results.insert(result_id, cursor) {
+            let _ = evicted.close(client); // A cursor that can't be closed goes with the session
+        }
+    }
+    // The query itself succeeded; a plan that can't be had is only reported
+    match analysis {
+        Ok(analysis) => analysis.apply(&mut result),
+        Err(e) => result.insights = Some(vec![format!("Plan unavailable: {}", e)]),
+    }
+    Ok(result)
+}
 
-            let mut result = conn.query_iter(sql_clean.clone()).map_err(|e| format!("MySQL query error: {}", e))?;
-            let cols_set = result.columns();
-            let cols_slice = cols_set.as_ref();
-            let columns: Vec<String> = cols_slice.iter().map(|c| c.name_str().to_string()).collect();
-            let mut rows: Vec<HashMap<String, serde_json::Value>> = Vec::new();
-            while let Some(row_res) = result.next() {
-                let row = row_res.map_err(|e| format!("MySQL row error: {}", e))?;
-                let mut obj = HashMap::with_capacity(columns.len());
-                for (i, col) in columns.iter().enumerate() {
-                    let v = row.as_ref(i).cloned();
-                    let json_v = match v {
-                        Some(MyValue::NULL) | None => serde_json::Value::Null,
-                        Some(MyValue::Bytes(b)) => serde_json::Value::String(String::from_utf8_lossy(&b).to_string()),
-                        Some(MyValue::Int(i)) => serde_json::json!(i),
-                        Some(MyValue::UInt(u)) => serde_json::json!(u),
-                        Some(MyValue::Float(f)) => serde_json::json!(f),
-                        Some(MyValue::Double(d)) => serde_json::json!(d),
-                        Some(MyValue::Date(y,m,d,h,mi,s,us)) => serde_json::json!(format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}", y,m,d,h,mi,s,us)),
-                        Some(MyValue::Time(is_neg, d, h, m, s, us)) => {
-                            let total_hours: u32 = (h as u32) + d * 24;
-                            serde_json::json!(format!("{}{:02}:{:02}:{:02}.{:06}", if is_neg {"-"} else {""}, total_hours, m, s, us))
-                        }
-                    };
-                    obj.insert(col.clone(), json_v);
+#[tauri::command]
+async fn cancel_query(state: tauri::State<'_, AppState>, query_id: String) -> Result<(), String> {
+    // Take what the cancel needs out of the registry so no lock is held while it runs
+    let handle = {
+        let guard = state.queries.running.lock().map_err(|_| "state poisoned".to_string())?;
+        let running = guard.get(&query_id).ok_or_else(|| "No running query with this id".to_string())?;
+        running.cancelled.store(true, Ordering::SeqCst);
+        match &running.handle {
+            // Still queued; it stops as soon as it gets its connection
+            None => return Ok(()),
+            Some(CancelHandle::Sqlite(h)) => {
+                h.interrupt();
+                return Ok(());
+            }
+            Some(CancelHandle::Postgres(token)) => CancelHandle::Postgres(token.clone()),
+            Some(CancelHandle::Mysql { config, thread_id }) => CancelHandle::Mysql { config: config.clone(), thread_id: *thread_id },
+        }
+    };
+    // Both cancels block on a second connection to the server
+    tauri::async_runtime::spawn_blocking(move || match handle {
+        CancelHandle::Postgres(token) => token
+            .cancel_query(NoTls)
+            .map_err(|e| format!("Postgres cancel error: {}", e)),
+        CancelHandle::Mysql { config, thread_id } => match connect_network(&config)? {
+            NetworkClient::Mysql(mut conn) => conn
+                .query_drop(format!("KILL QUERY {}", thread_id))
+                .map_err(|e| format!("MySQL cancel error: {}", e)),
+            NetworkClient::Postgres(_) => Err("Unsupported driver".into()),
+        },
+        CancelHandle::Sqlite(_) => Ok(()),
+    })
+    .await
+    .map_err(|e| format!("Worker error: {}", e))?
+}
+
+// SQLite reports its own transaction state; only savepoint names are tracked
+fn sqlite_transaction_status(conn: &Connection, tracked: &mut TransactionStatus) -> TransactionStatus {
+    if conn.is_autocommit() {
+        *tracked = TransactionStatus::default();
+        return tracked.clone();
+    }
+    // SAFETY: the caller holds the connection lock
+    let state = unsafe { rusqlite::ffi::sqlite3_txn_state(conn.handle(), std::ptr::null()) };
+    tracked.active = true;
+    tracked.pending_changes = state == rusqlite::ffi::SQLITE_TXN_WRITE;
+    tracked.clone()
+}
+
+// Runs `op` (or just reports the status) on whichever session owns the connection id
+async fn transaction_command(state: &AppState, connection_id: String, op: Option<TransactionOp>) -> Result<TransactionStatus, String> {
+    if let Ok(session) = sqlite_session(state, &connection_id) {
+        return run_blocking(state, &connection_id, move || {
+            let conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+            let mut tx = session.transaction.lock().map_err(|_| "connection poisoned".to_string())?;
+            let status = sqlite_transaction_status(&conn, &mut tx);
+            let Some(op) = op else { return Ok(status) };
+            let sql = op.sql(&status, Dialect::Sqlite)?;
+            conn.execute_batch(&sql).map_err(|e| format!("Execution error: {}", e))?;
+            op.apply(&mut tx);
+            Ok(sqlite_transaction_status(&conn, &mut tx))
+        })
+        .await;
+    }
+    let session = network_session(state, &connection_id)?;
+    run_blocking(state, &connection_id, move || {
+        let mut client = session.client.lock().map_err(|_| "connection poisoned".to_string())?;
+        let mut tx = session.transaction.lock().map_err(|_| "connection poisoned".to_string())?;
+        let Some(op) = op else { return Ok(tx.clone()) };
+        match &mut *client {
+            NetworkClient::Postgres(c) => {
+                let sql = op.sql(&tx, Dialect::Postgres)?;
+                c.batch_execute(&sql).map_err(|e| format!("Postgres query error: {}", e))?;
+            }
+            NetworkClient::Mysql(c) => {
+                let sql = op.sql(&tx, Dialect::Mysql)?;
+                c.query_drop(&sql).map_err(|e| format!("MySQL query error: {}", e))?;
+            }
+        }
+        op.apply(&mut tx);
+        Ok(tx.clone())
+    })
+    .await
+}
+
+#[tauri::command]
+async fn transaction_status(state: tauri::State<'_, AppState>, connection_id: String) -> Result<TransactionStatus, String> {
+    transaction_command(&state, connection_id, None).await
+}
+
+#[tauri::command]
+async fn begin_transaction(state: tauri::State<'_, AppState>, connection_id: String) -> Result<TransactionStatus, String> {
+    transaction_command(&state, connection_id, Some(TransactionOp::Begin)).await
+}
+
+#[tauri::command]
+async fn commit(state: tauri::State<'_, AppState>, connection_id: String) -> Result<TransactionStatus, String> {
+    transaction_command(&state, connection_id, Some(TransactionOp::Commit)).await
+}
+
+#[tauri::command]
+async fn rollback(state: tauri::State<'_, AppState>, connection_id: String) -> Result<TransactionStatus, String> {
+    transaction_command(&state, connection_id, Some(TransactionOp::Rollback)).await
+}
+
+#[tauri::command]
+async fn savepoint(state: tauri::State<'_, AppState>, connection_id: String, name: String) -> Result<TransactionStatus, String> {
+    transaction_command(&state, connection_id, Some(TransactionOp::Savepoint(name))).await
+}
+
+#[tauri::command]
+async fn release_savepoint(state: tauri::State<'_, AppState>, connection_id: String, name: String) -> Result<TransactionStatus, String> {
+    transaction_command(&state, connection_id, Some(TransactionOp::Release(name))).await
+}
+
+#[tauri::command]
+async fn rollback_to_savepoint(state: tauri::State<'_, AppState>, connection_id: String, name: String) -> Result<TransactionStatus, String> {
+    transaction_command(&state, connection_id, Some(TransactionOp::RollbackTo(name))).await
+}
+
+fn network_query(client: &mut NetworkClient, sql: &str, encoding: RowEncoding, tx: &mut TransactionStatus) -> Result<QueryResult, String> {
+    // Rows only; network_run adds the plan of a lone query
+    let dialect = match client {
+        NetworkClient::Postgres(_) => Dialect::Postgres,
+        NetworkClient::Mysql(_) => Dialect::Mysql,
+    };
+    let statements = sql_split::split(sql, dialect);
+    // Point at the failing statement when running a script
+    let script = statements.len() > 1;
+    let located = |idx: usize, e: String| if script { format!("Statement {}: {}", idx + 1, e) } else { e };
+    let mut sets: Vec<StatementResult> = Vec::new();
+
+    match client {
+        NetworkClient::Postgres(client) => {
+            // A script runs as one transaction, so a failing statement undoes the ones
+            // before it; not when one is already open or a statement can't run in one
+            let atomic = script && !tx.active && statements.iter().all(|s| pg_transactional(&s.sql));
+            if atomic {
+                client.batch_execute("BEGIN").map_err(|e| format!("Postgres query error: {}", e))?;
+            }
+            // Extended protocol, one statement at a time, so values come back typed
+            let mut run = || -> Result<(), String> {
+                for (idx, statement) in statements.iter().enumerate() {
+                    let started = Instant::now();
+                    let stmt = client
+                        .prepare(&statement.sql)
+                        .map_err(|e| located(idx, format!("Postgres query error: {}", e)))?;
+                    if stmt.columns().is_empty() {
+                        let affected = client
+                            .execute(&stmt, &[])
+                            .map_err(|e| located(idx, format!("Postgres query error: {}", e)))?;
+                        sets.push(StatementResult { columns: vec![], column_info: vec![], rows: encode_rows(&[], vec![], encoding), row_count: 0, affected_rows: Some(affected), elapsed_ms: started.elapsed().as_millis() as u64 });
+                        tx.observe(&statement.sql, statement.kind != StatementKind::Query, dialect);
+                        continue;
+                    }
+                    let rows: Vec<Vec<serde_json::Value>> = client
+                        .query(&stmt, &[])
+                        .map_err(|e| located(idx, format!("Postgres query error: {}", e)))?
+                        .iter()
+                        .map(pg_values::row_to_values)
+                        .collect();
+                    let elapsed_ms = started.elapsed().as_millis() as u64;
+                    tx.observe(&statement.sql, statement.kind != StatementKind::Query, dialect);
+                    let columns: Vec<String> = stmt.columns().iter().map(|c| c.name().to_string()).collect();
+                    sets.push(StatementResult {
+                        column_info: pg_column_info(client, &stmt),
+                        row_count: rows.len() as u64,
+                        rows: encode_rows(&columns, rows, encoding),
+                        columns,
+                        affected_rows: None,
+                        elapsed_ms,
+                    });
                 }
-                rows.push(obj);
+                Ok(())
+            };
+            let ran = run();
+            if atomic {
+                let ended = client
+                    .batch_execute(if ran.is_ok() { "COMMIT" } else { "ROLLBACK" })
+                    .map_err(|e| format!("Postgres query error: {}", e));
+                // The statement's own error says more than a failed ROLLBACK would
+                ran.and(ended)?;
+            } else {
+                ran?;
+            }
+        }
+        NetworkClient::Mysql(conn) => {
+            // Text protocol; a CALL can produce several result sets of its own
+            for (idx, statement) in statements.iter().enumerate() {
+                let mut started = Instant::now();
+                let mut result = conn
+                    .query_iter(&statement.sql)
+                    .map_err(|e| located(idx, format!("MySQL query error: {}", e)))?;
+                while let Some(mut set) = result.iter() {
+                    let cols_set = set.columns();
+                    let cols_slice = cols_set.as_ref();
+                    let columns: Vec<String> = cols_slice.iter().map(|c| c.name_str().to_string()).collect();
+                    let column_info = mysql_column_info(cols_slice);
+                    let mut rows: Vec<Vec<serde_json::Value>> = Vec::new();
+                    for row_res in set.by_ref() {
+                        let row = row_res.map_err(|e| located(idx, format!("MySQL row error: {}", e)))?;
+                        rows.push(mysql_row_to_values(&row));
+                    }
+                    sets.push(StatementResult {
+                        row_count: rows.len() as u64,
+                        affected_rows: if columns.is_empty() { Some(set.affected_rows()) } else { None },
+                        rows: encode_rows(&columns, rows, encoding),
+                        columns,
+                        column_info,
+                        elapsed_ms: started.elapsed().as_millis() as u64,
+                    });
+                    started = Instant::now();
+                }
+                drop(result);
+                tx.observe(&statement.sql, statement.kind != StatementKind::Query, dialect);
             }
-            Ok(QueryResult { columns, rows, total_rows: None, plan_steps: None, insights: None, plan_tables: None, rows_scanned_estimate: None })
         }
-        _ => Err("Unsupported driver".into())
     }
+    Ok(statements_result(sets, encoding))
+}
+
+// Whether a Postgres statement can share a transaction with the rest of a script:
+// not transaction control, nor what Postgres refuses inside a transaction block
+fn pg_transactional(sql: &str) -> bool {
+    let tokens = sql_split::tokens(sql, Dialect::Postgres);
+    let is = |i: usize, keywords: &[&str]| tokens.get(i).is_some_and(|t| keywords.iter().any(|k| t.eq_ignore_ascii_case(k)));
+    let control = is(0, &["BEGIN", "START", "COMMIT", "END", "ABORT", "ROLLBACK", "SAVEPOINT", "RELEASE"]) || (is(0, &["PREPARE"]) && is(1, &["TRANSACTION"]));
+    let standalone = is(0, &["VACUUM"])
+        || (is(0, &["CREATE", "DROP", "ALTER"]) && is(1, &["DATABASE", "TABLESPACE", "SYSTEM"]))
+        || tokens.iter().any(|t| t.eq_ignore_ascii_case("CONCURRENTLY"));
+    !control && !standalone
 }
 
 #[tauri::command]
-fn run_sqlite_query_raw(
-    state: tauri::State<AppState>,
+async fn run_sqlite_query_raw(
+    state: tauri::State<'_, AppState>,
     connection_id: String,
     sql: String,
+    query_id: Option<String>,
+    encoding: Option<RowEncoding>,
+    exact_counts: Option<bool>, // Plan table sizes by COUNT(*) rather than from statistics
 ) -> Result<QueryResult, String> {
-    let file_path = {
-        let guard = state.sqlite_files.lock().map_err(|_| "state poisoned".to_string())?;
-        guard.get(&connection_id).cloned().ok_or_else(|| "No SQLite file registered for this connection".to_string())?
-    };
+    let session = sqlite_session(&state, &connection_id)?;
+    let running = state.queries.begin(query_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.attach(CancelHandle::Sqlite(conn.get_interrupt_handle()))?;
+        let mut tx = session.transaction.lock().map_err(|_| "connection poisoned".to_string())?;
+        let mut results = session.results.lock().map_err(|_| "connection poisoned".to_string())?;
+        let exact_counts = exact_counts.unwrap_or(false);
+        running.finish(sqlite_query(&conn, &sql, None, encoding.unwrap_or_default(), exact_counts, &mut tx, &mut results))
+    })
+    .await
+}
 
-    let conn = Connection::open_with_flags(file_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
-        .map_err(|e| format!("Failed to open DB: {}", e))?;
+// Runs the statements of a script in order, one result set each
+fn sqlite_script(conn: &Connection, sql: &str, encoding: RowEncoding, tx: &mut TransactionStatus) -> Result<Vec<StatementResult>, String> {
+    // sqlite3_changes keeps the last DML count across DDL, so diff the running total instead
+    // SAFETY: the caller holds the connection lock
+    let total_changes = || unsafe { rusqlite::ffi::sqlite3_total_changes64(conn.handle()) } as u64;
+    let mut sets: Vec<StatementResult> = Vec::new();
+    let mut batch = rusqlite::Batch::new(conn, sql);
+    while let Some(mut stmt) = batch.next().map_err(|e| format!("Prepare error: {}", e))? {
+        let started = Instant::now();
+        let changes_before = total_changes();
+        let columns: Vec<String> = stmt.column_names().into_iter().map(|s| s.to_string()).collect();
+        let text = stmt.expanded_sql().unwrap_or_default();
+        let column_info = if columns.is_empty() { vec![] } else { sqlite_column_info(conn, &text) };
+        let mut rows: Vec<Vec<serde_json::Value>> = Vec::new();
+        let mut query = stmt.query([]).map_err(|e| format!("Execution error: {}", e))?;
+        while let Some(row) = query.next().map_err(|e| format!("Row error: {}", e))? {
+            rows.push(row_to_values(row, columns.len()).map_err(|e| format!("Row error: {}", e))?);
+        }
+        drop(query);
+        tx.observe(&text, !stmt.readonly(), Dialect::Sqlite);
+        let affected_rows = if stmt.readonly() { None } else { Some(total_changes() - changes_before) };
+        sets.push(StatementResult {
+            row_count: rows.len() as u64,
+            rows: encode_rows(&columns, rows, encoding),
+            columns,
+            column_info,
+            affected_rows,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+    Ok(sets)
+}
+
+// Rows per streamed batch unless the caller asks otherwise
+const DEFAULT_STREAM_BATCH: usize = 500;
+// Batches the backend may send ahead of the frontend's stream_ack
+const STREAM_WINDOW: u64 = 4;
+
+// Messages pushed through the channel passed to the stream_* commands
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase", tag = "event", content = "data")]
+enum QueryStreamEvent {
+    Started { query_id: String, columns: Vec<String>, column_info: Vec<ColumnInfo> },
+    Rows { seq: u64, rows: EncodedRows },
+    Finished { query_id: String, row_count: u64, batch_count: u64, elapsed_ms: u64 },
+}
 
-    // Normalize SQL: remove trailing semicolons and whitespace
-    let sql_clean: String = sql.trim().trim_end_matches(';').trim().to_string();
-    let trimmed = sql_clean.trim_start();
-    let is_select = trimmed.to_lowercase().starts_with("select");
+// Batches rows into the channel, waiting for acknowledgements once
+// STREAM_WINDOW batches are outstanding.
+struct StreamSink<'a> {
+    channel: &'a Channel<QueryStreamEvent>,
+    running: &'a QueryGuard,
+    batch_size: usize,
+    encoding: RowEncoding,
+    columns: Vec<String>,
+    pending: Vec<Vec<serde_json::Value>>,
+    sent: u64,
+    row_count: u64,
+    started_at: Instant,
+}
+
+impl<'a> StreamSink<'a> {
+    fn new(channel: &'a Channel<QueryStreamEvent>, running: &'a QueryGuard, batch_size: Option<u32>, encoding: RowEncoding) -> Self {
+        let batch_size = batch_size.map(|b| b as usize).filter(|b| *b > 0).unwrap_or(DEFAULT_STREAM_BATCH);
+        StreamSink {
+            channel,
+            running,
+            batch_size,
+            encoding,
+            columns: Vec::new(),
+            pending: Vec::with_capacity(batch_size),
+            sent: 0,
+            row_count: 0,
+            started_at: Instant::now(),
+        }
+    }
+
+    fn start(&mut self, columns: Vec<String>, column_info: Vec<ColumnInfo>) -> Result<(), String> {
+        self.columns = columns.clone();
+        self.channel
+            .send(QueryStreamEvent::Started { query_id: self.running.query_id.clone(), columns, column_info })
+            .map_err(|e| format!("Stream send error: {}", e))
+    }
+
+    fn push(&mut self, row: Vec<serde_json::Value>) -> Result<(), String> {
+        self.pending.push(row);
+        self.row_count += 1;
+        if self.pending.len() >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.wait_for_window()?;
+        let rows = std::mem::replace(&mut self.pending, Vec::with_capacity(self.batch_size));
+        self.sent += 1;
+        self.channel
+            .send(QueryStreamEvent::Rows { seq: self.sent, rows: encode_rows(&self.columns, rows, self.encoding) })
+            .map_err(|e| format!("Stream send error: {}", e))
+    }
 
-    if !is_select {
-        // Non-select: execute and return empty result
-        conn.execute_batch(&sql_clean)
+    fn wait_for_window(&self) -> Result<(), String> {
+        let flow = &self.running.flow;
+        let mut acked = flow.acked.lock().map_err(|_| "state poisoned".to_string())?;
+        while self.sent >= *acked + STREAM_WINDOW {
+            if self.running.cancelled.load(Ordering::SeqCst) {
+                return Err(QUERY_CANCELLED.to_string());
+            }
+            acked = flow
+                .changed
+                .wait_timeout(acked, Duration::from_millis(100))
+                .map_err(|_| "state poisoned".to_string())?
+                .0;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), String> {
+        self.flush()?;
+        self.channel
+            .send(QueryStreamEvent::Finished {
+                query_id: self.running.query_id.clone(),
+                row_count: self.row_count,
+                batch_count: self.sent,
+                elapsed_ms: self.started_at.elapsed().as_millis() as u64,
+            })
+            .map_err(|e| format!("Stream send error: {}", e))
+    }
+}
+
+#[tauri::command]
+fn stream_ack(state: tauri::State<AppState>, query_id: String, seq: u64) -> Result<(), String> {
+    let guard = state.queries.running.lock().map_err(|_| "state poisoned".to_string())?;
+    // The stream may already have finished; late acks are fine
+    if let Some(running) = guard.get(&query_id) {
+        let mut acked = running.flow.acked.lock().map_err(|_| "state poisoned".to_string())?;
+        if seq > *acked {
+            *acked = seq;
+            running.flow.changed.notify_all();
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn stream_sqlite_query(
+    state: tauri::State<'_, AppState>,
+    connection_id: String,
+    sql: String,
+    batch_size: Option<u32>,
+    query_id: Option<String>,
+    encoding: Option<RowEncoding>,
+    on_event: Channel<QueryStreamEvent>,
+) -> Result<(), String> {
+    let session = sqlite_session(&state, &connection_id)?;
+    let running = state.queries.begin(query_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.attach(CancelHandle::Sqlite(conn.get_interrupt_handle()))?;
+        let sink = StreamSink::new(&on_event, &running, batch_size, encoding.unwrap_or_default());
+        let mut tx = session.transaction.lock().map_err(|_| "connection poisoned".to_string())?;
+        let mut results = session.results.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.check(sqlite_stream_query(&conn, &sql, sink, &mut tx, &mut results))
+    })
+    .await
+}
+
+fn sqlite_stream_query(conn: &Connection, sql: &str, mut sink: StreamSink<'_>, tx: &mut TransactionStatus, results: &mut OpenResults<SqliteCursor>) -> Result<(), String> {
+    // Earlier statements of a script just run; the last one is streamed
+    let statements = sql_split::split(sql, Dialect::Sqlite);
+    let Some((last, leading)) = statements.split_last() else {
+        sink.start(vec![], vec![])?;
+        return sink.finish();
+    };
+    // As in sqlite_query, open cursors outlive only a lone plain query; they
+    // would lock out DDL and writes
+    if !matches!(statements.as_slice(), [only] if only.kind == StatementKind::Query) {
+        results.clear();
+    }
+    for statement in leading {
+        conn.execute_batch(&statement.sql)
             .map_err(|e| format!("Execution error: {}", e))?;
-        return Ok(QueryResult { columns: vec![], rows: vec![], total_rows: None, plan_steps: None, insights: None, plan_tables: None, rows_scanned_estimate: None });
+        tx.observe(&statement.sql, statement.kind != StatementKind::Query, Dialect::Sqlite);
     }
 
-    // Run the query as-is (no pagination)
     let mut stmt = conn
-        .prepare(&sql_clean)
+        .prepare_cached(&last.sql)
         .map_err(|e| format!("Prepare error: {}", e))?;
-
     let col_names: Vec<String> = stmt
         .column_names()
         .into_iter()
         .map(|s| s.to_string())
         .collect();
+    let column_count = col_names.len();
+    sink.start(col_names, sqlite_column_info(conn, &last.sql))?;
 
-    let rows_iter = stmt
-        .query_map([], |row| row_to_map(row, &col_names))
-        .map_err(|e| format!("Query error: {}", e))?;
-
-    let mut rows: Vec<HashMap<String, serde_json::Value>> = Vec::new();
-    for item in rows_iter {
-        rows.push(item.map_err(|e| format!("Row error: {}", e))?);
-    }
-
-    // Total rows (for SELECT queries)
-    let count_sql = format!("SELECT COUNT(*) AS count FROM ( {} )", sql_clean);
-    let total_rows: u64 = conn
-        .query_row(&count_sql, [], |r| r.get::<_, i64>(0))
-        .map(|v| v as u64)
-        .map_err(|e| format!("Count error: {}", e))?;
-
-    // Explain query plan for insights
-    let mut plan_steps: Vec<String> = Vec::new();
-    let mut explain_stmt = conn
-        .prepare(&format!("EXPLAIN QUERY PLAN {}", sql_clean))
-        .map_err(|e| format!("Explain prepare error: {}", e))?;
-    let explain_iter = explain_stmt
-        .query_map([], |row| {
-            // columns: id, parent, notused, detail
-            let detail: String = row.get(3)?;
-            Ok(detail)
-        })
-        .map_err(|e| format!("Explain query error: {}", e))?;
-    for s in explain_iter {
-        plan_steps.push(s.map_err(|e| format!("Explain row error: {}", e))?);
-    }
-
-    // Derive simple insights from plan
-    let mut insights: Vec<String> = Vec::new();
-    let mut table_access: std::collections::HashMap<String, String> = std::collections::HashMap::new();
-    for step in &plan_steps {
-        let upper = step.to_uppercase();
-        if upper.contains("USING INDEX") {
-            insights.push(format!("Index used: {}", step));
-        }
-        if upper.contains("SCAN ") && !upper.contains("SEARCH ") {
-            insights.push(format!("Full scan: {}", step));
-        }
-
-        // Extract table name if present
-        let access = if upper.contains("SEARCH ") { "SEARCH" } else if upper.contains("SCAN ") { "SCAN" } else { "UNKNOWN" };
-        let mut pos = None;
-        if let Some(i) = upper.find("SEARCH ") { pos = Some(i + 7); }
-        if let Some(i) = upper.find("SCAN ") { pos = Some(i + 5); }
-        if let Some(mut start) = pos {
-            // skip whitespace
-            let chars: Vec<char> = step.chars().collect();
-            while start < chars.len() && chars[start].is_whitespace() { start += 1; }
-            // Optional TABLE keyword (case-insensitive)
-            let tail_upper = step[start..].to_uppercase();
-            if tail_upper.starts_with("TABLE ") {
-                start += 6; // len("TABLE ")
+    let mut rows = stmt.query([]).map_err(|e| format!("Query error: {}", e))?;
+    while let Some(row) = rows.next().map_err(|e| format!("Row error: {}", e))? {
+        sink.push(row_to_values(row, column_count).map_err(|e| format!("Row error: {}", e))?)?;
+    }
+    drop(rows);
+    tx.observe(&last.sql, !stmt.readonly(), Dialect::Sqlite);
+    sink.finish()
+}
+
+#[tauri::command]
+async fn stream_network_query(
+    state: tauri::State<'_, AppState>,
+    connection_id: String,
+    sql: String,
+    batch_size: Option<u32>,
+    query_id: Option<String>,
+    encoding: Option<RowEncoding>,
+    on_event: Channel<QueryStreamEvent>,
+) -> Result<(), String> {
+    let session = network_session(&state, &connection_id)?;
+    let running = state.queries.begin(query_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let mut client = session.client.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.attach(network_cancel_handle(&session, &client))?;
+        let sink = StreamSink::new(&on_event, &running, batch_size, encoding.unwrap_or_default());
+        let mut tx = session.transaction.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.check(network_stream_query(&mut client, &sql, sink, &mut tx))
+    })
+    .await
+}
+
+fn network_stream_query(client: &mut NetworkClient, sql: &str, mut sink: StreamSink<'_>, tx: &mut TransactionStatus) -> Result<(), String> {
+    let dialect = match client {
+        NetworkClient::Postgres(_) => Dialect::Postgres,
+        NetworkClient::Mysql(_) => Dialect::Mysql,
+    };
+    // Earlier statements of a script just run; the last one is streamed
+    let statements = sql_split::split(sql, dialect);
+    let Some((last, leading)) = statements.split_last() else {
+        sink.start(vec![], vec![])?;
+        return sink.finish();
+    };
+
+    match client {
+        NetworkClient::Postgres(client) => {
+            for statement in leading {
+                client.batch_execute(&statement.sql).map_err(|e| format!("Postgres query error: {}", e))?;
+                tx.observe(&statement.sql, statement.kind != StatementKind::Query, dialect);
             }
-            // Capture identifier or quoted name
-            let mut name = String::new();
-            if start < chars.len() && (chars[start] == '"' || chars[start] == '\'') {
-                let quote = chars[start];
-                start += 1;
-                while start < chars.len() && chars[start] != quote { name.push(chars[start]); start += 1; }
-            } else {
-                while start < chars.len() {
-                    let ch = chars[start];
-                    if ch.is_alphanumeric() || ch == '_' { name.push(ch); start += 1; }
-                    else { break; }
+            let stmt = client.prepare(&last.sql).map_err(|e| format!("Postgres query error: {}", e))?;
+            let column_info = pg_column_info(client, &stmt);
+            let columns: Vec<String> = stmt.columns().iter().map(|c| c.name().to_string()).collect();
+            sink.start(columns, column_info)?;
+            if last.kind != StatementKind::Query || tx.active {
+                // Writes (e.g. INSERT ... RETURNING) run whole rather than through the portal
+                // below, and an open transaction can't host the portal's own; fetch everything instead
+                for row in client.query(&stmt, &[]).map_err(|e| format!("Postgres query error: {}", e))? {
+                    sink.push(pg_values::row_to_values(&row))?;
                 }
+                tx.observe(&last.sql, last.kind != StatementKind::Query, dialect);
+                return sink.finish();
             }
-            if !name.is_empty() {
-                // Prefer SCAN over SEARCH as a stronger signal
-                let existing = table_access.get(&name).cloned();
-                match existing.as_deref() {
-                    Some("SCAN") => { /* keep SCAN */ }
-                    _ => { table_access.insert(name, access.to_string()); }
+            // Pull from a portal so only one batch is held in memory at a time
+            let mut tx = client.transaction().map_err(|e| format!("Postgres query error: {}", e))?;
+            let portal = tx.bind(&stmt, &[]).map_err(|e| format!("Postgres query error: {}", e))?;
+            loop {
+                let rows = tx
+                    .query_portal(&portal, sink.batch_size as i32)
+                    .map_err(|e| format!("Postgres query error: {}", e))?;
+                let fetched = rows.len();
+                for row in &rows {
+                    sink.push(pg_values::row_to_values(row))?;
+                }
+                if fetched < sink.batch_size {
+                    break;
                 }
             }
+            // A query can still write (nextval(), volatile functions, DELETE ... RETURNING
+            // in a WITH), so keep its effects as autocommit would; an error or a
+            // cancelled stream drops the transaction and rolls it back
+            tx.commit().map_err(|e| format!("Postgres query error: {}", e))?;
+            sink.finish()
+        }
+        NetworkClient::Mysql(conn) => {
+            for statement in leading {
+                conn.query_drop(&statement.sql).map_err(|e| format!("MySQL query error: {}", e))?;
+                tx.observe(&statement.sql, statement.kind != StatementKind::Query, dialect);
+            }
+            // The text protocol result is read from the socket row by row
+            let mut result = conn.query_iter(&last.sql).map_err(|e| format!("MySQL query error: {}", e))?;
+            let columns: Vec<String> = result.columns().as_ref().iter().map(|c| c.name_str().to_string()).collect();
+            let column_info = mysql_column_info(result.columns().as_ref());
+            sink.start(columns, column_info)?;
+            for row_res in result.by_ref() {
+                let row = row_res.map_err(|e| format!("MySQL row error: {}", e))?;
+                sink.push(mysql_row_to_values(&row))?;
+            }
+            drop(result);
+            tx.observe(&last.sql, last.kind != StatementKind::Query, dialect);
+            sink.finish()
         }
     }
-    if insights.is_empty() && !plan_steps.is_empty() {
-        insights.push("Plan analyzed with no obvious full scans".to_string());
-    }
+}
 
-    // For tables referenced, collect total row counts and estimate scanned rows for full scans
-    let mut plan_tables: Vec<PlanTableInfo> = Vec::new();
-    let mut rows_scanned_estimate: u64 = 0;
-    for (table, access) in table_access.into_iter() {
-        // Skip SQLite internal tables
-        if table.starts_with("sqlite_") { continue; }
-        let total: Option<u64> = match conn.query_row(&format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "")), [], |r| r.get::<_, i64>(0)) {
-            Ok(v) => Some(v as u64),
-            Err(_) => None,
-        };
-        if access == "SCAN" {
-            if let Some(t) = total { rows_scanned_estimate = rows_scanned_estimate.saturating_add(t); }
-        }
-        plan_tables.push(PlanTableInfo { table, access, total_rows: total });
-    }
-    // Always return a numeric estimate (0 if no full scans detected)
-    let rows_scanned_estimate_opt = Some(rows_scanned_estimate);
-
-    Ok(QueryResult {
-        columns: col_names,
-        rows,
-        total_rows: Some(total_rows),
-        plan_steps: Some(plan_steps),
-        insights: Some(insights),
-        plan_tables: if plan_tables.is_empty() { None } else { Some(plan_tables) },
-        rows_scanned_estimate: rows_scanned_estimate_opt,
+// Index suggestions for a lone query, checked on a copy of the schema unless `validate` is false
+#[tauri::command]
+async fn suggest_sqlite_indexes(state: tauri::State<'_, AppState>, connection_id: String, sql: String, validate: Option<bool>) -> Result<Vec<IndexSuggestion>, String> {
+    let session = sqlite_session(&state, &connection_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+        let statements = sql_split::split(&sql, Dialect::Sqlite);
+        let [only] = statements.as_slice() else { return Err("Index suggestions need a single query".to_string()) };
+        let (plan, _) = sqlite_plan::explain(&conn, &only.sql)?;
+        Ok(sqlite_advisor::suggest(&conn, &only.sql, &plan, validate.unwrap_or(true)))
     })
+    .await
 }
 
+// Refreshes the planner statistics that plan row estimates come from; every table when none are named
 #[tauri::command]
-fn sqlite_table_summary(state: tauri::State<AppState>, connection_id: String, table_name: String) -> Result<DbSchemaSummary, String> {
-    let file_path = {
-        let guard = state.sqlite_files.lock().map_err(|_| "state poisoned".to_string())?;
-        guard.get(&connection_id).cloned().ok_or_else(|| "No SQLite file registered for this connection".to_string())?
-    };
-    let conn = Connection::open_with_flags(file_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
-        .map_err(|e| format!("Failed to open DB: {}", e))?;
-
-    // tables list (only the requested table)
-    let mut table_stmt = conn
-        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name = ?1")
-        .map_err(|e| format!("Prepare tables error: {}", e))?;
-    let table_names_iter = table_stmt
-        .query_map(params![table_name], |row| row.get::<_, String>(0))
-        .map_err(|e| format!("Tables query error: {}", e))?;
-    let mut tables: Vec<TableInfo> = Vec::new();
-    let mut foreign_keys: Vec<ForeignKeyEdge> = Vec::new();
-    for t in table_names_iter {
-        let table_name = t.map_err(|e| format!("Error reading table name: {}", e))?;
-        // columns
-        let mut col_stmt = conn
-            .prepare(&format!("PRAGMA table_info('{}')", table_name.replace("'", "''")))
-            .map_err(|e| format!("Prepare table_info error: {}", e))?;
-        let cols_iter = col_stmt
-            .query_map([], |row| {
-                let name: String = row.get(1)?;
-                let data_type: Option<String> = row.get::<_, Option<String>>(2)?;
-                let not_null: i64 = row.get(3)?;
-                let pk: i64 = row.get(5)?;
-                Ok(TableColumn { name, data_type, not_null: not_null != 0, pk: pk != 0 })
-            })
-            .map_err(|e| format!("table_info query error: {}", e))?;
-        let mut cols: Vec<TableColumn> = Vec::new();
-        let mut pk_cols: Vec<String> = Vec::new();
-        for c in cols_iter {
-            let col = c.map_err(|e| format!("column row error: {}", e))?;
-            if col.pk { pk_cols.push(col.name.clone()); }
-            cols.push(col);
-        }
-        let mut keys: Vec<TableKey> = Vec::new();
-        if !pk_cols.is_empty() {
-            keys.push(TableKey { key_type: "PRIMARY_KEY".into(), name: None, columns: pk_cols.clone(), ref_table: None, ref_columns: None, unique: None });
-        }
-
-        // foreign keys per table; also accumulate global edges
-        let pragma = format!("PRAGMA foreign_key_list('{}')", table_name.replace("'", "''"));
-        let mut fk_stmt = conn
-            .prepare(&pragma)
-            .map_err(|e| format!("Prepare foreign_key_list error: {}", e))?;
-        let mut groups: HashMap<i64, (String, Vec<String>, Vec<String>)> = HashMap::new();
-        let fk_iter = fk_stmt
-            .query_map([], |row| {
-                // columns: id, seq, table, from, to, on_update, on_delete, match
-                let id: i64 = row.get(0)?;
-                let ref_table: String = row.get(2)?;
-                let from_col: String = row.get(3)?;
-                let to_col: String = row.get(4)?;
-                Ok((id, ref_table, from_col, to_col))
-            })
-            .map_err(|e| format!("foreign_key_list query error: {}", e))?;
-        for r in fk_iter {
-            let (id, ref_table, from_col, to_col) = r.map_err(|e| format!("fk row error: {}", e))?;
-            let entry = groups.entry(id).or_insert_with(|| (ref_table, Vec::new(), Vec::new()));
-            entry.1.push(from_col);
-            entry.2.push(to_col);
-        }
-        for (_id, (ref_table, from_cols, to_cols)) in groups.into_iter() {
-            keys.push(TableKey { key_type: "FOREIGN_KEY".into(), name: None, columns: from_cols.clone(), ref_table: Some(ref_table.clone()), ref_columns: Some(to_cols.clone()), unique: None });
-            foreign_keys.push(ForeignKeyEdge {
-                from_table: table_name.clone(),
-                from_columns: from_cols,
-                to_table: ref_table,
-                to_columns: to_cols,
-            });
-        }
-
-        // indexes
-        let mut idx_stmt = conn
-            .prepare(&format!("PRAGMA index_list('{}')", table_name.replace("'", "''")))
-            .map_err(|e| format!("Prepare index_list error: {}", e))?;
-        let idx_iter = idx_stmt
-            .query_map([], |row| {
-                // seq, name, unique, origin, partial
-                let name: String = row.get(1)?;
-                let unique: i64 = row.get(2)?;
-                Ok((name, unique != 0))
-            })
-            .map_err(|e| format!("index_list query error: {}", e))?;
-        for idx in idx_iter {
-            let (idx_name, unique) = idx.map_err(|e| format!("index row error: {}", e))?;
-            if idx_name.starts_with("sqlite_autoindex") { continue; }
-            let mut info_stmt = conn
-                .prepare(&format!("PRAGMA index_info('{}')", idx_name.replace("'", "''")))
-                .map_err(|e| format!("Prepare index_info error: {}", e))?;
-            let info_iter = info_stmt
-                .query_map([], |row| {
-                    // seqno, cid, name
-                    let col_name: String = row.get(2)?;
-                    Ok(col_name)
-                })
-                .map_err(|e| format!("index_info query error: {}", e))?;
-            let mut index_cols: Vec<String> = Vec::new();
-            for c in info_iter { index_cols.push(c.map_err(|e| format!("index_info row error: {}", e))?); }
-            keys.push(TableKey { key_type: "INDEX".into(), name: Some(idx_name), columns: index_cols, ref_table: None, ref_columns: None, unique: Some(unique) });
-        }
+async fn sqlite_analyze(state: tauri::State<'_, AppState>, connection_id: String, tables: Option<Vec<String>>, query_id: Option<String>) -> Result<(), String> {
+    let session = sqlite_session(&state, &connection_id)?;
+    let running = state.queries.begin(query_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.attach(CancelHandle::Sqlite(conn.get_interrupt_handle()))?;
+        let statements = match tables {
+            // Plan tables may go by an alias, which ANALYZE would reject
+            Some(tables) => tables
+                .iter()
+                .filter(|t| conn.query_row("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1", params![t], |_| Ok(())).is_ok())
+                .map(|t| format!("ANALYZE \"{}\"", t.replace('"', "\"\"")))
+                .collect(),
+            None => vec!["ANALYZE".to_string()],
+        };
+        let analyzed = statements.iter().try_for_each(|s| conn.execute_batch(s)).map_err(|e| format!("Analyze error: {}", e));
+        running.check(analyzed)
+    })
+    .await
+}
 
-        tables.push(TableInfo { name: table_name, columns: cols, keys });
-    }
+// Rows whose foreign keys point at missing parents, per key; every table when none is named
+#[tauri::command]
+async fn sqlite_foreign_key_check(state: tauri::State<'_, AppState>, connection_id: String, table: Option<String>, query_id: Option<String>) -> Result<Vec<ForeignKeyViolations>, String> {
+    let session = sqlite_session(&state, &connection_id)?;
+    let running = state.queries.begin(query_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+        running.attach(CancelHandle::Sqlite(conn.get_interrupt_handle()))?;
+        running.check(sqlite_schema::foreign_key_check(&conn, table.as_deref()))
+    })
+    .await
+}
 
-    Ok(DbSchemaSummary { tables, foreign_keys })
+#[tauri::command]
+async fn sqlite_table_summary(state: tauri::State<'_, AppState>, connection_id: String, table_name: String) -> Result<DbSchemaSummary, String> {
+    let session = sqlite_session(&state, &connection_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+        sqlite_schema::summary(&conn, Some(&table_name), true)
+    })
+    .await
 }
 
+#[tauri::command]
+async fn sqlite_schema_summary(state: tauri::State<'_, AppState>, connection_id: String, include_shadow: Option<bool>) -> Result<DbSchemaSummary, String> {
+    let session = sqlite_session(&state, &connection_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let conn = session.conn.lock().map_err(|_| "connection poisoned".to_string())?;
+        sqlite_schema::summary(&conn, None, include_shadow.unwrap_or(false))
+    })
+    .await
+}
 
 #[tauri::command]
-fn sqlite_schema_summary(state: tauri::State<AppState>, connection_id: String) -> Result<DbSchemaSummary, String> {
-    let file_path = {
-        let guard = state.sqlite_files.lock().map_err(|_| "state poisoned".to_string())?;
-        guard.get(&connection_id).cloned().ok_or_else(|| "No SQLite file registered for this connection".to_string())?
-    };
-    let conn = Connection::open_with_flags(file_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
-        .map_err(|e| format!("Failed to open DB: {}", e))?;
-
-    // tables list
-    let mut table_stmt = conn
-        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
-        .map_err(|e| format!("Prepare tables error: {}", e))?;
-    let table_names_iter = table_stmt
-        .query_map([], |row| row.get::<_, String>(0))
-        .map_err(|e| format!("Tables query error: {}", e))?;
-    let mut tables: Vec<TableInfo> = Vec::new();
-    let mut foreign_keys: Vec<ForeignKeyEdge> = Vec::new();
-    for t in table_names_iter {
-        let table_name = t.map_err(|e| format!("Error reading table name: {}", e))?;
-        // columns
-        let mut col_stmt = conn
-            .prepare(&format!("PRAGMA table_info('{}')", table_name.replace("'", "''")))
-            .map_err(|e| format!("Prepare table_info error: {}", e))?;
-        let cols_iter = col_stmt
-            .query_map([], |row| {
-                let name: String = row.get(1)?;
-                let data_type: Option<String> = row.get::<_, Option<String>>(2)?;
-                let not_null: i64 = row.get(3)?;
-                let pk: i64 = row.get(5)?;
-                Ok(TableColumn { name, data_type, not_null: not_null != 0, pk: pk != 0 })
-            })
-            .map_err(|e| format!("table_info query error: {}", e))?;
-        let mut cols: Vec<TableColumn> = Vec::new();
-        let mut pk_cols: Vec<String> = Vec::new();
-        for c in cols_iter {
-            let col = c.map_err(|e| format!("column row error: {}", e))?;
-            if col.pk { pk_cols.push(col.name.clone()); }
-            cols.push(col);
-        }
-        let mut keys: Vec<TableKey> = Vec::new();
-        if !pk_cols.is_empty() {
-            keys.push(TableKey { key_type: "PRIMARY_KEY".into(), name: None, columns: pk_cols.clone(), ref_table: None, ref_columns: None, unique: None });
-        }
-
-        // foreign keys per table; also accumulate global edges
-        let pragma = format!("PRAGMA foreign_key_list('{}')", table_name.replace("'", "''"));
-        let mut fk_stmt = conn
-            .prepare(&pragma)
-            .map_err(|e| format!("Prepare foreign_key_list error: {}", e))?;
-        let mut groups: HashMap<i64, (String, Vec<String>, Vec<String>)> = HashMap::new();
-        let fk_iter = fk_stmt
-            .query_map([], |row| {
-                // columns: id, seq, table, from, to, on_update, on_delete, match
-                let id: i64 = row.get(0)?;
-                let ref_table: String = row.get(2)?;
-                let from_col: String = row.get(3)?;
-                let to_col: String = row.get(4)?;
-                Ok((id, ref_table, from_col, to_col))
-            })
-            .map_err(|e| format!("foreign_key_list query error: {}", e))?;
-        for r in fk_iter {
-            let (id, ref_table, from_col, to_col) = r.map_err(|e| format!("fk row error: {}", e))?;
-            let entry = groups.entry(id).or_insert_with(|| (ref_table, Vec::new(), Vec::new()));
-            entry.1.push(from_col);
-            entry.2.push(to_col);
-        }
-        for (_id, (ref_table, from_cols, to_cols)) in groups.into_iter() {
-            keys.push(TableKey { key_type: "FOREIGN_KEY".into(), name: None, columns: from_cols.clone(), ref_table: Some(ref_table.clone()), ref_columns: Some(to_cols.clone()), unique: None });
-            foreign_keys.push(ForeignKeyEdge {
-                from_table: table_name.clone(),
-                from_columns: from_cols,
-                to_table: ref_table,
-                to_columns: to_cols,
-            });
-        }
+async fn network_table_summary(state: tauri::State<'_, AppState>, connection_id: String, table_name: String) -> Result<DbSchemaSummary, String> {
+    let session = network_session(&state, &connection_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let mut client = session.client.lock().map_err(|_| "connection poisoned".to_string())?;
+        network_schema(&mut client, Some(&table_name))
+    })
+    .await
+}
 
-        // indexes
-        let mut idx_stmt = conn
-            .prepare(&format!("PRAGMA index_list('{}')", table_name.replace("'", "''")))
-            .map_err(|e| format!("Prepare index_list error: {}", e))?;
-        let idx_iter = idx_stmt
-            .query_map([], |row| {
-                // seq, name, unique, origin, partial
-                let name: String = row.get(1)?;
-                let unique: i64 = row.get(2)?;
-                Ok((name, unique != 0))
-            })
-            .map_err(|e| format!("index_list query error: {}", e))?;
-        for idx in idx_iter {
-            let (idx_name, unique) = idx.map_err(|e| format!("index row error: {}", e))?;
-            if idx_name.starts_with("sqlite_autoindex") { continue; }
-            let mut info_stmt = conn
-                .prepare(&format!("PRAGMA index_info('{}')", idx_name.replace("'", "''")))
-                .map_err(|e| format!("Prepare index_info error: {}", e))?;
-            let info_iter = info_stmt
-                .query_map([], |row| {
-                    // seqno, cid, name
-                    let col_name: String = row.get(2)?;
-                    Ok(col_name)
-                })
-                .map_err(|e| format!("index_info query error: {}", e))?;
-            let mut index_cols: Vec<String> = Vec::new();
-            for c in info_iter { index_cols.push(c.map_err(|e| format!("index_info row error: {}", e))?); }
-            keys.push(TableKey { key_type: "INDEX".into(), name: Some(idx_name), columns: index_cols, ref_table: None, ref_columns: None, unique: Some(unique) });
-        }
+#[tauri::command]
+async fn network_schema_summary(state: tauri::State<'_, AppState>, connection_id: String) -> Result<DbSchemaSummary, String> {
+    let session = network_session(&state, &connection_id)?;
+    run_blocking(&state, &connection_id, move || {
+        let mut client = session.client.lock().map_err(|_| "connection poisoned".to_string())?;
+        network_schema(&mut client, None)
+    })
+    .await
+}
 
-        tables.push(TableInfo { name: table_name, columns: cols, keys });
+fn network_schema(client: &mut NetworkClient, table: Option<&str>) -> Result<DbSchemaSummary, String> {
+    match client {
+        NetworkClient::Postgres(client) => pg_schema::summary(client, table),
+        NetworkClient::Mysql(conn) => mysql_schema::summary(conn, table),
     }
-
-    Ok(DbSchemaSummary { tables, foreign_keys })
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -778,7 +1817,7 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .manage(AppState::default())
-        .invoke_handler(tauri::generate_handler![sqlite_open, run_sqlite_query, run_sqlite_query_raw, sqlite_table_summary, sqlite_schema_summary, run_network_query])
+        .invoke_handler(tauri::generate_handler![sqlite_open, sqlite_close, sqlite_reopen, run_sqlite_query, run_sqlite_query_raw, sqlite_table_summary, sqlite_schema_summary, network_open, network_close, run_network_query, cancel_query, set_connection_concurrency, stream_sqlite_query, stream_network_query, stream_ack, transaction_status, begin_transaction, commit, rollback, savepoint, release_savepoint, rollback_to_savepoint, fetch_page, count_result, close_result, suggest_sqlite_indexes, sqlite_analyze, network_table_summary, network_schema_summary, sqlite_foreign_key_check])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,434 @@
+// MySQL plan analysis from EXPLAIN FORMAT=JSON, plus EXPLAIN ANALYZE where the
+// server has it.
+//
+// The JSON plan nests query blocks, sort/group/dedup operations and nested loops
+// around "table" objects, one per table access. MariaDB writes the same shape
+// with a few different keys ("rows" for "rows_examined_per_scan", numbers where
+// MySQL writes strings). EXPLAIN ANALYZE (MySQL 8.0.18+) only has a text tree,
+// which replaces the JSON tree when asked for.
+
+use mysql::prelude::Queryable;
+use mysql::Conn as MyConn;
+use serde_json::Value;
+
+use crate::sql_split::{self, Dialect};
+use crate::{sqlite_advisor, PlanAnalysis, PlanNode, PlanTableInfo};
+
+// A filter keeping less than this share of the rows it examines is reported
+const LOW_FILTERED_PCT: f64 = 10.0;
+
+// Only `run` executes the statement (EXPLAIN ANALYZE). A failed EXPLAIN leaves
+// a MySQL transaction usable, so the plain one needs no savepoint. The run is
+// rolled back, which can't undo writes to non-transactional (MyISAM) tables
+pub(crate) fn analyze(conn: &mut MyConn, sql: &str, run: bool, in_transaction: bool) -> Result<PlanAnalysis, String> {
+    let my_err = |e: mysql::Error| format!("Explain error: {}", e);
+    let json: Option<String> = conn.query_first(format!("EXPLAIN FORMAT=JSON {}", sql)).map_err(my_err)?;
+    let document: Value = serde_json::from_str(json.as_deref().unwrap_or("null")).map_err(|e| format!("Explain error: {}", e))?;
+    let root = document.get("query_block").ok_or_else(|| "Explain error: empty plan".to_string())?;
+
+    let mut analysis = PlanAnalysis { plan: None, steps: Vec::new(), insights: Vec::new(), tables: Vec::new(), rows_scanned_estimate: 0, rows_scanned_exact: false, index_suggestions: None };
+    let mut plan = query_block(root, &mut analysis);
+
+    base_tables(&mut analysis, sql);
+    for table in analysis.tables.iter_mut() {
+        table.total_rows = table_estimate(conn, &table.table);
+    }
+    if analysis.insights.is_empty() {
+        analysis.insights.push("Plan analyzed with no obvious full scans".to_string());
+    }
+
+    // MariaDB reports 10.x and up and has its own ANALYZE statement
+    let version = conn.server_version();
+    if run && ((8, 0, 18)..(10, 0, 0)).contains(&version) {
+        let (open, close) = if in_transaction {
+            ("SAVEPOINT fluxquery_explain", "ROLLBACK TO SAVEPOINT fluxquery_explain; RELEASE SAVEPOINT fluxquery_explain")
+        } else {
+            ("START TRANSACTION", "ROLLBACK")
+        };
+        conn.query_drop(open).map_err(my_err)?;
+        let explained: Result<Option<String>, _> = conn.query_first(format!("EXPLAIN ANALYZE {}", sql));
+        conn.query_drop(close).map_err(my_err)?;
+        if let Some(tree) = analyze_tree(&explained.map_err(my_err)?.unwrap_or_default()) {
+            plan = tree;
+        }
+    }
+    analysis.plan = Some(plan);
+    Ok(analysis)
+}
+
+// A query block and what it contains; tables are collected into `analysis` on the way
+fn query_block(block: &Value, analysis: &mut PlanAnalysis) -> PlanNode {
+    let id = block.get("select_id").and_then(Value::as_u64);
+    let mut node = plan_node(id.map_or("Query block".to_string(), |id| format!("Query block #{}", id)));
+    node.total_cost = block.get("cost_info").and_then(|c| number(c.get("query_cost")));
+    node.children = operations(block, analysis);
+    node
+}
+
+// Operations nested in a JSON object
+fn operations(object: &Value, analysis: &mut PlanAnalysis) -> Vec<PlanNode> {
+    let Some(fields) = object.as_object() else { return Vec::new() };
+    let mut nodes = Vec::new();
+    for (key, value) in fields {
+        match key.as_str() {
+            "table" => nodes.push(table_access(value, analysis)),
+            "nested_loop" => {
+                let mut join = plan_node("Nested loop".to_string());
+                for item in value.as_array().into_iter().flatten() {
+                    join.children.extend(operations(item, analysis));
+                }
+                nodes.push(join);
+            }
+            "ordering_operation" | "grouping_operation" | "duplicates_removal" | "windowing" => {
+                let flag = |name: &str| value.get(name).and_then(Value::as_bool).unwrap_or(false);
+                let mut operation = match key.as_str() {
+                    "ordering_operation" => "Sort",
+                    "grouping_operation" => "Group",
+                    "duplicates_removal" => "Remove duplicates",
+                    _ => "Window",
+                }
+                .to_string();
+                if flag("using_filesort") {
+                    operation.push_str(" (filesort)");
+                    analysis.insights.push(format!("{} sorts rows without an index", operation));
+                }
+                if flag("using_temporary_table") {
+                    analysis.insights.push(format!("{} uses a temporary table", operation));
+                }
+                let mut node = plan_node(operation);
+                node.children = operations(value, analysis);
+                nodes.push(node);
+            }
+            "union_result" => {
+                let mut union = plan_node("Union".to_string());
+                for spec in value.get("query_specifications").and_then(Value::as_array).into_iter().flatten() {
+                    if let Some(block) = spec.get("query_block") {
+                        union.children.push(query_block(block, analysis));
+                    }
+                }
+                nodes.push(union);
+            }
+            "attached_subqueries" | "optimized_away_subqueries" | "select_list_subqueries" | "having_subqueries" | "order_by_subqueries" | "group_by_subqueries" => {
+                for item in value.as_array().into_iter().flatten() {
+                    if let Some(block) = item.get("query_block") {
+                        nodes.push(query_block(block, analysis));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    nodes
+}
+
+fn table_access(table: &Value, analysis: &mut PlanAnalysis) -> PlanNode {
+    let text = |key: &str| table.get(key).and_then(Value::as_str).map(str::to_string);
+    let name = text("table_name").unwrap_or_default();
+    let access_type = text("access_type").unwrap_or_else(|| "UNKNOWN".to_string());
+    let key = text("key");
+    let rows = number(table.get("rows_examined_per_scan").or_else(|| table.get("rows")));
+    let filtered = number(table.get("filtered"));
+
+    let mut detail = format!("{} on {}", access_type, name);
+    if let Some(key) = &key {
+        detail.push_str(&format!(" using {}", key));
+    }
+    let figures: Vec<String> = [rows.map(|r| format!("rows {}", r)), filtered.map(|f| format!("filtered {}%", f))].into_iter().flatten().collect();
+    if !figures.is_empty() {
+        detail.push_str(&format!(" ({})", figures.join(", ")));
+    }
+
+    // ALL reads every row and index reads every index entry; the rest look rows up.
+    // Derived tables (<derived2>, <subquery3>, ...) are materialized results, not tables
+    let derived = name.starts_with('<');
+    let full_scan = access_type == "ALL" || access_type == "index";
+    if full_scan && !derived {
+        analysis.insights.push(format!("Full scan: {}", detail));
+        analysis.rows_scanned_estimate = analysis.rows_scanned_estimate.saturating_add(rows.unwrap_or(0.0) as u64);
+    } else if key.is_some() {
+        analysis.insights.push(format!("Index used: {}", detail));
+    }
+    let possible_keys = table.get("possible_keys").and_then(Value::as_array).filter(|keys| !keys.is_empty());
+    if let (Some(possible), None) = (possible_keys, &key) {
+        let names: Vec<&str> = possible.iter().filter_map(Value::as_str).collect();
+        analysis.insights.push(format!("No index used on {} although {} could apply", name, names.join(", ")));
+    }
+    if let (Some(rows), Some(filtered)) = (rows, filtered) {
+        if filtered < LOW_FILTERED_PCT && rows >= 1000.0 {
+            analysis.insights.push(format!("Filter on {} keeps {}% of the {} rows examined", name, filtered, rows));
+        }
+    }
+    analysis.steps.push(detail.clone());
+    if !derived {
+        analysis.tables.push(PlanTableInfo {
+            table: name.clone(),
+            access: if full_scan { "SCAN" } else { "SEARCH" }.to_string(),
+            total_rows: None,
+            total_rows_exact: false,
+            access_type: Some(access_type.clone()),
+            key: key.clone(),
+            rows_examined: rows.map(|r| r as u64),
+            filtered_pct: filtered,
+        });
+    }
+
+    let mut node = plan_node(access_type);
+    node.detail = Some(detail);
+    node.table = Some(name);
+    node.index = key;
+    node.estimated_rows = rows;
+    node.total_cost = table.get("cost_info").and_then(|c| number(c.get("prefix_cost")));
+    if let Some(block) = table.get("materialized_from_subquery").and_then(|m| m.get("query_block")) {
+        node.children.push(query_block(block, analysis));
+    }
+    node
+}
+
+// The JSON plan names a table by its alias when it has one
+fn base_tables(analysis: &mut PlanAnalysis, sql: &str) {
+    let refs = sqlite_advisor::table_refs(&sql_split::tokens(sql, Dialect::Mysql));
+    for table in analysis.tables.iter_mut() {
+        if let Some(name) = sqlite_advisor::aliased_table(&refs, &table.table) {
+            table.table = name.clone();
+        }
+    }
+}
+
+// MySQL writes costs and percentages as strings
+fn number(value: Option<&Value>) -> Option<f64> {
+    match value? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn plan_node(operation: String) -> PlanNode {
+    PlanNode {
+        operation,
+        detail: None,
+        table: None,
+        index: None,
+        startup_cost: None,
+        total_cost: None,
+        estimated_rows: None,
+        actual_rows: None,
+        actual_time_ms: None,
+        loops: None,
+        rows_removed: None,
+        blocks_hit: None,
+        blocks_read: None,
+        flags: Vec::new(),
+        children: Vec::new(),
+    }
+}
+
+// Tree from EXPLAIN ANALYZE text, one "-> " line per node, nested by indentation:
+//   -> Filter: (t.a > 1)  (cost=0.55 rows=2) (actual time=0.031..0.035 rows=2 loops=1)
+//       -> Table scan on t  (cost=0.55 rows=3) (actual time=0.029..0.033 rows=3 loops=1)
+fn analyze_tree(text: &str) -> Option<PlanNode> {
+    // Open ancestors of the next line, with their indentation
+    let mut stack: Vec<(usize, PlanNode)> = Vec::new();
+    let mut root = None;
+    for line in text.lines() {
+        let Some(body) = line.trim_start().strip_prefix("-> ") else { continue };
+        let indent = line.len() - line.trim_start().len();
+        let node = analyze_line(body);
+        while stack.last().is_some_and(|(depth, _)| *depth >= indent) {
+            let (_, done) = stack.pop()?;
+            match stack.last_mut() {
+                Some((_, parent)) => parent.children.push(done),
+                None => root = Some(done),
+            }
+        }
+        stack.push((indent, node));
+    }
+    while let Some((_, done)) = stack.pop() {
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.push(done),
+            None => root = Some(done),
+        }
+    }
+    root
+}
+
+fn analyze_line(body: &str) -> PlanNode {
+    let label = body.find("  (").map_or(body, |i| &body[..i]).trim();
+    // Figures following `prefix` in the line, e.g. "cost=0.25..0.55 rows=3"
+    let figures = |prefix: &str| body.find(prefix).map(|i| &body[i + prefix.len()..]).map(|rest| rest.split(')').next().unwrap_or(""));
+    let field = |figures: &str, key: &str| {
+        figures.split_whitespace().find_map(|part| part.strip_prefix(key)).map(str::to_string)
+    };
+    let word_after = |marker: &str| label.find(marker).and_then(|i| label[i + marker.len()..].split_whitespace().next()).map(str::to_string);
+
+    let operation = label.split([':', '(']).next().unwrap_or(label);
+    let operation = operation.split(" on ").next().unwrap_or(operation).trim();
+    let mut node = plan_node(operation.to_string());
+    node.detail = Some(label.to_string());
+    node.table = word_after(" on ");
+    node.index = word_after(" using ").filter(|_| label.contains(" on "));
+    if let Some(cost) = figures("(cost=") {
+        let range = cost.split_whitespace().next().unwrap_or("");
+        let (startup, total) = range.split_once("..").unwrap_or(("", range));
+        node.startup_cost = startup.parse().ok();
+        node.total_cost = total.parse().ok();
+        node.estimated_rows = field(cost, "rows=").and_then(|r| r.parse().ok());
+    }
+    if let Some(actual) = figures("(actual time=") {
+        let range = actual.split_whitespace().next().unwrap_or("");
+        node.actual_time_ms = range.split_once("..").and_then(|(_, last)| last.parse().ok());
+        node.actual_rows = field(actual, "rows=").and_then(|r| r.parse().ok());
+        node.loops = field(actual, "loops=").and_then(|l| l.parse().ok());
+    }
+    node
+}
+
+// Row count InnoDB keeps in the data dictionary; an estimate, refreshed by ANALYZE TABLE
+fn table_estimate(conn: &mut MyConn, table: &str) -> Option<u64> {
+    conn.exec_first("SELECT TABLE_ROWS FROM information_schema.TABLES WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?", (table,))
+        .ok()
+        .flatten()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // EXPLAIN FORMAT=JSON of a join, as MySQL 8.0 writes it
+    const JOIN_PLAN: &str = r#"{
+      "query_block": {
+        "select_id": 1,
+        "cost_info": { "query_cost": "1.10" },
+        "nested_loop": [
+          {
+            "table": {
+              "table_name": "o",
+              "access_type": "ALL",
+              "possible_keys": ["customer_id"],
+              "rows_examined_per_scan": 3,
+              "rows_produced_per_join": 1,
+              "filtered": "33.33",
+              "cost_info": { "read_cost": "0.45", "eval_cost": "0.10", "prefix_cost": "0.55", "data_read_per_join": "24" },
+              "used_columns": ["id", "customer_id", "total"],
+              "attached_condition": "((`shop`.`o`.`total` > 100) and (`shop`.`o`.`customer_id` is not null))"
+            }
+          },
+          {
+            "table": {
+              "table_name": "c",
+              "access_type": "eq_ref",
+              "possible_keys": ["PRIMARY"],
+              "key": "PRIMARY",
+              "used_key_parts": ["id"],
+              "key_length": "4",
+              "ref": ["shop.o.customer_id"],
+              "rows_examined_per_scan": 1,
+              "rows_produced_per_join": 1,
+              "filtered": "100.00",
+              "cost_info": { "read_cost": "0.25", "eval_cost": "0.10", "prefix_cost": "0.90", "data_read_per_join": "16" },
+              "used_columns": ["id", "name"]
+            }
+          }
+        ]
+      }
+    }"#;
+
+    const JOIN_SQL: &str = "SELECT c.name, o.total FROM orders o JOIN customers AS c ON c.id = o.customer_id WHERE o.total > 100";
+
+    fn analyzed(json: &str, sql: &str) -> (PlanNode, PlanAnalysis) {
+        let document: Value = serde_json::from_str(json).unwrap();
+        let mut analysis = PlanAnalysis { plan: None, steps: Vec::new(), insights: Vec::new(), tables: Vec::new(), rows_scanned_estimate: 0, rows_scanned_exact: false, index_suggestions: None };
+        let plan = query_block(&document["query_block"], &mut analysis);
+        base_tables(&mut analysis, sql);
+        (plan, analysis)
+    }
+
+    #[test]
+    fn aliases_map_to_their_tables() {
+        let (_, analysis) = analyzed(JOIN_PLAN, JOIN_SQL);
+        let tables: Vec<(&str, &str)> = analysis.tables.iter().map(|t| (t.table.as_str(), t.access.as_str())).collect();
+        assert_eq!(tables, [("orders", "SCAN"), ("customers", "SEARCH")]);
+        let (_, analysis) = analyzed(JOIN_PLAN, "SELECT * FROM o, c");
+        assert_eq!(analysis.tables[0].table, "o");
+    }
+
+    #[test]
+    fn nested_loop_tree_and_insights() {
+        let (plan, analysis) = analyzed(JOIN_PLAN, JOIN_SQL);
+        assert_eq!(plan.operation, "Query block #1");
+        assert_eq!(plan.total_cost, Some(1.1));
+        assert_eq!(plan.children.len(), 1);
+        let join = &plan.children[0];
+        assert_eq!(join.operation, "Nested loop");
+        let accesses: Vec<(&str, Option<&str>, Option<&str>)> = join.children.iter().map(|c| (c.operation.as_str(), c.table.as_deref(), c.index.as_deref())).collect();
+        assert_eq!(accesses, [("ALL", Some("o"), None), ("eq_ref", Some("c"), Some("PRIMARY"))]);
+        assert_eq!(join.children[1].total_cost, Some(0.9));
+        assert_eq!(analysis.steps, ["ALL on o (rows 3, filtered 33.33%)", "eq_ref on c using PRIMARY (rows 1, filtered 100%)"]);
+        assert_eq!(
+            analysis.insights,
+            [
+                "Full scan: ALL on o (rows 3, filtered 33.33%)",
+                "No index used on o although customer_id could apply",
+                "Index used: eq_ref on c using PRIMARY (rows 1, filtered 100%)",
+            ]
+        );
+        assert_eq!(analysis.rows_scanned_estimate, 3);
+        assert_eq!(analysis.tables[0].rows_examined, Some(3));
+        assert_eq!(analysis.tables[0].filtered_pct, Some(33.33));
+    }
+
+    #[test]
+    fn derived_tables_and_missing_row_counts() {
+        // The inner index scan has no rows_examined_per_scan
+        let json = r#"{"query_block": {"select_id": 1, "cost_info": {"query_cost": "2.50"},
+            "ordering_operation": {"using_filesort": true, "table": {
+                "table_name": "<derived2>", "access_type": "ALL", "rows_examined_per_scan": 2,
+                "materialized_from_subquery": {"using_temporary_table": true, "dependent": false, "cacheable": true,
+                    "query_block": {"select_id": 2, "table": {"table_name": "t", "access_type": "index", "key": "PRIMARY", "used_columns": ["id"]}}}}}}}"#;
+        let (plan, analysis) = analyzed(json, "SELECT * FROM (SELECT id FROM t) d ORDER BY id");
+        let sort = &plan.children[0];
+        assert_eq!(sort.operation, "Sort (filesort)");
+        let derived = &sort.children[0];
+        assert_eq!(derived.table.as_deref(), Some("<derived2>"));
+        assert_eq!(derived.children[0].operation, "Query block #2");
+        assert_eq!(derived.children[0].children[0].estimated_rows, None);
+        assert_eq!(analysis.steps, ["ALL on <derived2> (rows 2)", "index on t using PRIMARY"]);
+        assert_eq!(analysis.insights, ["Sort (filesort) sorts rows without an index", "Full scan: index on t using PRIMARY"]);
+        assert_eq!(analysis.tables.len(), 1);
+        assert_eq!((analysis.tables[0].table.as_str(), analysis.tables[0].rows_examined), ("t", None));
+        assert_eq!(analysis.rows_scanned_estimate, 0);
+    }
+
+    #[test]
+    fn mariadb_rows_and_numbers() {
+        let json = r#"{"query_block": {"select_id": 1, "table": {"table_name": "events", "access_type": "ALL", "rows": 20000, "filtered": 2.5, "attached_condition": "events.kind = 'x'"}}}"#;
+        let (_, analysis) = analyzed(json, "SELECT * FROM events WHERE kind = 'x'");
+        assert_eq!(analysis.insights, ["Full scan: ALL on events (rows 20000, filtered 2.5%)", "Filter on events keeps 2.5% of the 20000 rows examined"]);
+        assert_eq!(analysis.rows_scanned_estimate, 20000);
+    }
+
+    #[test]
+    fn analyze_text_tree() {
+        let text = "\
+-> Nested loop inner join  (cost=1.60 rows=1) (actual time=0.052..0.060 rows=2 loops=1)
+    -> Filter: ((o.total > 100) and (o.customer_id is not null))  (cost=0.55 rows=1) (actual time=0.034..0.039 rows=2 loops=1)
+        -> Table scan on o  (cost=0.55 rows=3) (actual time=0.031..0.035 rows=3 loops=1)
+    -> Single-row index lookup on c using PRIMARY (id=o.customer_id)  (cost=0.35 rows=1) (actual time=0.008..0.008 rows=1 loops=2)
+";
+        let root = analyze_tree(text).unwrap();
+        assert_eq!(root.operation, "Nested loop inner join");
+        assert_eq!((root.startup_cost, root.total_cost, root.estimated_rows), (None, Some(1.6), Some(1.0)));
+        assert_eq!((root.actual_time_ms, root.actual_rows, root.loops), (Some(0.06), Some(2.0), Some(1)));
+        assert_eq!(root.children.len(), 2);
+        let filter = &root.children[0];
+        assert_eq!(filter.operation, "Filter");
+        assert_eq!(filter.detail.as_deref(), Some("Filter: ((o.total > 100) and (o.customer_id is not null))"));
+        assert_eq!(filter.children.len(), 1);
+        assert_eq!((filter.children[0].operation.as_str(), filter.children[0].table.as_deref()), ("Table scan", Some("o")));
+        let lookup = &root.children[1];
+        assert_eq!(lookup.operation, "Single-row index lookup");
+        assert_eq!((lookup.table.as_deref(), lookup.index.as_deref(), lookup.loops), (Some("c"), Some("PRIMARY"), Some(2)));
+        assert!(analyze_tree("EXPLAIN\n").is_none());
+    }
+}
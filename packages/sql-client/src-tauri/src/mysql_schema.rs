@@ -0,0 +1,159 @@
+// MySQL schema introspection in the same shape as sqlite_schema, read from
+// information_schema for the connection's current database. Unique indexes
+// double as unique constraints in MySQL, so like SQLite they are listed as
+// unique INDEX keys.
+
+use std::collections::HashMap;
+
+use mysql::prelude::Queryable;
+use mysql::Conn as MyConn;
+
+use crate::{DbSchemaSummary, ForeignKeyEdge, TableColumn, TableInfo, TableKey};
+
+// TABLE_NAME, COLUMN_NAME, COLUMN_TYPE, IS_NULLABLE, COLUMN_KEY, COLUMN_DEFAULT, COLLATION_NAME, EXTRA, GENERATION_EXPRESSION
+type ColumnRow = (String, String, String, String, String, Option<String>, Option<String>, String, Option<String>);
+// TABLE_NAME, CONSTRAINT_NAME, COLUMN_NAME, REFERENCED_TABLE_SCHEMA, REFERENCED_TABLE_NAME, REFERENCED_COLUMN_NAME, UPDATE_RULE, DELETE_RULE, MATCH_OPTION
+type ForeignKeyRow = (String, String, String, String, String, String, String, String, String);
+
+// Every table and view of the current database, or only `table` when given
+pub(crate) fn summary(conn: &mut MyConn, table: Option<&str>) -> Result<DbSchemaSummary, String> {
+    let database: Option<String> = conn.query_first("SELECT DATABASE()").map_err(|e| format!("Database query error: {}", e))?.flatten();
+    let Some(database) = database else { return Err("No database selected".to_string()) };
+
+    let mut tables: Vec<TableInfo> = conn
+        .exec_map(
+            "SELECT TABLE_NAME, TABLE_TYPE, ENGINE, TABLE_COLLATION, AUTO_INCREMENT FROM information_schema.TABLES \
+             WHERE TABLE_SCHEMA = ? AND (? IS NULL OR TABLE_NAME = ?) ORDER BY TABLE_NAME",
+            (&database, table, table),
+            |(name, table_type, engine, collation, auto_increment): (String, String, Option<String>, Option<String>, Option<u64>)| TableInfo {
+                name,
+                columns: Vec::new(),
+                keys: Vec::new(),
+                schema: Some(database.clone()),
+                kind: if table_type.ends_with("VIEW") { "VIEW" } else { "TABLE" }.into(),
+                engine,
+                collation,
+                auto_increment,
+                module: None,
+                flags: Vec::new(),
+            },
+        )
+        .map_err(|e| format!("Tables query error: {}", e))?;
+    let positions: HashMap<String, usize> = tables.iter().enumerate().map(|(i, t)| (t.name.clone(), i)).collect();
+
+    // columns; EXTRA holds auto_increment and "STORED GENERATED" / "VIRTUAL GENERATED"
+    // (MariaDB: PERSISTENT), next to "DEFAULT_GENERATED" for expression defaults
+    let columns: Vec<ColumnRow> = conn
+        .exec(
+            "SELECT TABLE_NAME, COLUMN_NAME, COLUMN_TYPE, IS_NULLABLE, COLUMN_KEY, COLUMN_DEFAULT, COLLATION_NAME, EXTRA, GENERATION_EXPRESSION \
+             FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = ? AND (? IS NULL OR TABLE_NAME = ?) ORDER BY TABLE_NAME, ORDINAL_POSITION",
+            (&database, table, table),
+        )
+        .map_err(|e| format!("Columns query error: {}", e))?;
+    for (table_name, name, data_type, nullable, column_key, default_value, collation, extra, expression) in columns {
+        let Some(&at) = positions.get(&table_name) else { continue };
+        let extra = extra.to_uppercase();
+        let generated = if extra.contains("STORED GENERATED") || extra.contains("PERSISTENT") {
+            Some("STORED".to_string())
+        } else if extra.contains("VIRTUAL GENERATED") {
+            Some("VIRTUAL".to_string())
+        } else {
+            None
+        };
+        tables[at].columns.push(TableColumn {
+            name,
+            data_type: Some(data_type),
+            not_null: nullable == "NO",
+            pk: column_key == "PRI",
+            pk_ordinal: None, // set from the PRIMARY index below
+            default_value,
+            collation,
+            auto_increment: extra.contains("AUTO_INCREMENT"),
+            generation_expression: expression.filter(|_| generated.is_some()),
+            generated,
+        });
+    }
+
+    // indexes, one row per column in index order; functional key parts have no column
+    let index_rows: Vec<(String, String, i64, Option<String>)> = conn
+        .exec(
+            "SELECT TABLE_NAME, INDEX_NAME, NON_UNIQUE, COLUMN_NAME FROM information_schema.STATISTICS \
+             WHERE TABLE_SCHEMA = ? AND (? IS NULL OR TABLE_NAME = ?) ORDER BY TABLE_NAME, INDEX_NAME = 'PRIMARY' DESC, INDEX_NAME, SEQ_IN_INDEX",
+            (&database, table, table),
+        )
+        .map_err(|e| format!("Indexes query error: {}", e))?;
+    for (table_name, index_name, non_unique, column) in index_rows {
+        let Some(&at) = positions.get(&table_name) else { continue };
+        let column = column.unwrap_or_else(|| "(expression)".to_string());
+        let keys = &mut tables[at].keys;
+        match keys.last_mut() {
+            Some(key) if key.name.as_deref() == Some(index_name.as_str()) => key.columns.push(column),
+            _ => {
+                let primary = index_name == "PRIMARY";
+                keys.push(TableKey {
+                    key_type: if primary { "PRIMARY_KEY" } else { "INDEX" }.into(),
+                    name: Some(index_name),
+                    columns: vec![column],
+                    unique: (!primary).then_some(non_unique == 0),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    // columns' positions in their primary key
+    for info in &mut tables {
+        let Some(primary) = info.keys.iter().find(|k| k.key_type == "PRIMARY_KEY") else { continue };
+        for (n, name) in primary.columns.iter().enumerate() {
+            if let Some(column) = info.columns.iter_mut().find(|c| &c.name == name) {
+                column.pk_ordinal = Some(n as u32 + 1);
+            }
+        }
+    }
+
+    // foreign keys, one row per column pair, by constraint name; also accumulate global edges.
+    // MySQL has no deferred constraint checks
+    let fk_rows: Vec<ForeignKeyRow> = conn
+        .exec(
+            "SELECT k.TABLE_NAME, k.CONSTRAINT_NAME, k.COLUMN_NAME, k.REFERENCED_TABLE_SCHEMA, k.REFERENCED_TABLE_NAME, k.REFERENCED_COLUMN_NAME, \
+             r.UPDATE_RULE, r.DELETE_RULE, r.MATCH_OPTION \
+             FROM information_schema.KEY_COLUMN_USAGE k JOIN information_schema.REFERENTIAL_CONSTRAINTS r \
+             ON r.CONSTRAINT_SCHEMA = k.CONSTRAINT_SCHEMA AND r.TABLE_NAME = k.TABLE_NAME AND r.CONSTRAINT_NAME = k.CONSTRAINT_NAME \
+             WHERE k.TABLE_SCHEMA = ? AND (? IS NULL OR k.TABLE_NAME = ?) ORDER BY k.TABLE_NAME, k.CONSTRAINT_NAME, k.ORDINAL_POSITION",
+            (&database, table, table),
+        )
+        .map_err(|e| format!("Foreign keys query error: {}", e))?;
+    let mut named_edges: Vec<(String, ForeignKeyEdge)> = Vec::new();
+    for (table_name, name, from_col, to_schema, to_table, to_col, on_update, on_delete, match_type) in fk_rows {
+        match named_edges.last_mut() {
+            Some((last, edge)) if *last == name && edge.from_table == table_name => {
+                edge.from_columns.push(from_col);
+                edge.to_columns.push(to_col);
+            }
+            _ => named_edges.push((
+                name,
+                ForeignKeyEdge {
+                    from_table: table_name,
+                    from_columns: vec![from_col],
+                    to_table,
+                    to_columns: vec![to_col],
+                    from_schema: Some(database.clone()),
+                    to_schema: Some(to_schema),
+                    on_update: Some(on_update),
+                    on_delete: Some(on_delete),
+                    match_type: Some(match_type),
+                    deferrable: Some(false),
+                    initially_deferred: Some(false),
+                },
+            )),
+        }
+    }
+    let mut foreign_keys: Vec<ForeignKeyEdge> = Vec::new();
+    for (name, edge) in named_edges {
+        let Some(&at) = positions.get(&edge.from_table) else { continue };
+        tables[at].keys.push(TableKey::foreign_key(&edge, Some(name)));
+        foreign_keys.push(edge);
+    }
+
+    Ok(DbSchemaSummary { tables, foreign_keys, schemas: Some(vec![database]), triggers: None })
+}
@@ -0,0 +1,143 @@
+// Postgres and MySQL results read page by page, the network side of OpenResults.
+//
+// Postgres keeps the query in a server-side SCROLL cursor and each page is a
+// MOVE ABSOLUTE + FETCH. Outside a transaction the cursor is declared WITH HOLD,
+// which runs the query to completion when DECLARE's implicit transaction commits,
+// so counting it afterwards is cheap. Inside the user's transaction it lives until
+// that transaction ends.
+//
+// MySQL has no cursors outside stored programs and a streamed result has to be
+// read to the end before the connection takes another command, so a MySQL
+// "cursor" is a buffered result: rows are read off the socket once, kept
+// undecoded, and decoded a page at a time. Only the first MYSQL_BUFFERED_ROWS
+// are kept; the rest are read to count them and can't be paged to.
+
+use mysql::{prelude::Queryable, Row as MyRow};
+use postgres::Client as PgClient;
+
+use crate::open_results::RESULT_CLOSED;
+use crate::sql_split::{self, Dialect};
+use crate::{mysql_column_info, mysql_row_to_values, pg_column_info, pg_values, ColumnInfo, NetworkClient};
+
+// Rows of a MySQL result kept for paging
+const MYSQL_BUFFERED_ROWS: usize = 100_000;
+
+pub(crate) struct NetworkCursor {
+    pub(crate) columns: Vec<String>,
+    pub(crate) column_info: Vec<ColumnInfo>,
+    pub(crate) total_rows: Option<u64>, // Known once counted, or as soon as MySQL rows are read
+    source: CursorSource,
+}
+
+enum CursorSource {
+    Postgres { name: String },
+    MysqlBuffered { rows: Vec<MyRow> }, // The first MYSQL_BUFFERED_ROWS rows of the result
+}
+
+// Whether `sql` can be kept open as a cursor. Postgres won't declare one over a
+// query that locks rows (FOR UPDATE/SHARE) or writes in a WITH, so those run unpaged
+pub(crate) fn can_open(client: &NetworkClient, sql: &str) -> bool {
+    if matches!(client, NetworkClient::Mysql(_)) {
+        return true;
+    }
+    let tokens = sql_split::tokens(sql, Dialect::Postgres);
+    let is = |t: &str, keywords: &[&str]| keywords.iter().any(|k| t.eq_ignore_ascii_case(k));
+    let locks = tokens.windows(2).any(|w| is(w[0], &["FOR"]) && is(w[1], &["UPDATE", "SHARE", "NO", "KEY"]));
+    let writes = tokens.first().is_some_and(|t| is(t, &["WITH"])) && tokens.iter().any(|t| is(t, &["INSERT", "UPDATE", "DELETE", "MERGE"]));
+    !locks && !writes
+}
+
+impl NetworkCursor {
+    // `in_transaction` is whether the user has a transaction open on this session
+    pub(crate) fn open(client: &mut NetworkClient, sql: &str, result_id: &str, in_transaction: bool) -> Result<NetworkCursor, String> {
+        match client {
+            NetworkClient::Postgres(client) => {
+                let stmt = client.prepare(sql).map_err(|e| format!("Postgres query error: {}", e))?;
+                let columns = stmt.columns().iter().map(|c| c.name().to_string()).collect();
+                let column_info = pg_column_info(client, &stmt);
+                let name = format!("fluxquery_{}", result_id.replace('-', "_"));
+                let hold = if in_transaction { "" } else { " WITH HOLD" };
+                client
+                    .batch_execute(&format!("DECLARE {} SCROLL CURSOR{} FOR {}", name, hold, sql))
+                    .map_err(|e| format!("Postgres query error: {}", e))?;
+                Ok(NetworkCursor { columns, column_info, total_rows: None, source: CursorSource::Postgres { name } })
+            }
+            NetworkClient::Mysql(conn) => {
+                let mut result = conn.query_iter(sql).map_err(|e| format!("MySQL query error: {}", e))?;
+                let columns = result.columns().as_ref().iter().map(|c| c.name_str().to_string()).collect();
+                let column_info = mysql_column_info(result.columns().as_ref());
+                let mut rows: Vec<MyRow> = Vec::new();
+                let mut total = 0u64;
+                for row in result.by_ref() {
+                    let row = row.map_err(|e| format!("MySQL row error: {}", e))?;
+                    if rows.len() < MYSQL_BUFFERED_ROWS {
+                        rows.push(row);
+                    }
+                    total += 1;
+                }
+                Ok(NetworkCursor { columns, column_info, total_rows: Some(total), source: CursorSource::MysqlBuffered { rows } })
+            }
+        }
+    }
+
+    pub(crate) fn page(&mut self, client: &mut NetworkClient, page: u32, page_size: u32) -> Result<Vec<Vec<serde_json::Value>>, String> {
+        if page_size == 0 {
+            return Err("pageSize must be at least 1".to_string());
+        }
+        let start = (page as u64).saturating_mul(page_size as u64);
+        match (&self.source, client) {
+            (CursorSource::Postgres { name }, NetworkClient::Postgres(client)) => {
+                ensure_cursor(client, name)?;
+                let pg_err = |e: postgres::Error| format!("Postgres query error: {}", e);
+                client.batch_execute(&format!("MOVE ABSOLUTE {} IN {}", start, name)).map_err(pg_err)?;
+                let rows = client.query(&format!("FETCH FORWARD {} FROM {}", page_size, name), &[]).map_err(pg_err)?;
+                Ok(rows.iter().map(pg_values::row_to_values).collect())
+            }
+            (CursorSource::MysqlBuffered { rows }, _) => {
+                if start >= rows.len() as u64 && self.total_rows.is_some_and(|total| start < total) {
+                    return Err(format!("Only the first {} rows of a MySQL result can be paged; narrow the query with LIMIT", MYSQL_BUFFERED_ROWS));
+                }
+                let start = (start as usize).min(rows.len());
+                let end = start.saturating_add(page_size as usize).min(rows.len());
+                Ok(rows[start..end].iter().map(mysql_row_to_values).collect())
+            }
+            _ => Err(RESULT_CLOSED.to_string()),
+        }
+    }
+
+    pub(crate) fn count(&mut self, client: &mut NetworkClient) -> Result<u64, String> {
+        if let Some(total) = self.total_rows {
+            return Ok(total);
+        }
+        let (CursorSource::Postgres { name }, NetworkClient::Postgres(client)) = (&self.source, client) else {
+            return Err(RESULT_CLOSED.to_string());
+        };
+        ensure_cursor(client, name)?;
+        // MOVE reports how many rows it skipped; pages reposition with MOVE ABSOLUTE anyway
+        let pg_err = |e: postgres::Error| format!("Count error: {}", e);
+        client.batch_execute(&format!("MOVE ABSOLUTE 0 IN {}", name)).map_err(pg_err)?;
+        let total = client.execute(&format!("MOVE FORWARD ALL IN {}", name), &[]).map_err(pg_err)?;
+        self.total_rows = Some(total);
+        Ok(total)
+    }
+
+    // Releases the server-side cursor, if it still exists
+    pub(crate) fn close(self, client: &mut NetworkClient) -> Result<(), String> {
+        let (CursorSource::Postgres { name }, NetworkClient::Postgres(client)) = (self.source, client) else {
+            return Ok(());
+        };
+        match ensure_cursor(client, &name) {
+            Ok(()) => client.batch_execute(&format!("CLOSE {}", name)).map_err(|e| format!("Postgres query error: {}", e)),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+// A cursor declared inside a transaction disappears when it ends. Checked up front
+// because a failing FETCH or CLOSE would abort the user's open transaction.
+fn ensure_cursor(client: &mut PgClient, name: &str) -> Result<(), String> {
+    let open = client
+        .query_opt("SELECT 1 FROM pg_cursors WHERE name = $1", &[&name])
+        .map_err(|e| format!("Postgres query error: {}", e))?;
+    open.map(|_| ()).ok_or_else(|| RESULT_CLOSED.to_string())
+}
@@ -0,0 +1,61 @@
+// Results kept open between commands so later pages are read without running
+// the query again. Each session owns one registry of its own cursor type.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Error for a result id that was closed, evicted or never opened
+pub(crate) const RESULT_CLOSED: &str = "Result is no longer open";
+
+// Results a session keeps open; opening another evicts the oldest
+const MAX_OPEN_RESULTS: usize = 8;
+
+// Result ids are unique across sessions, so an id sent to the wrong connection
+// is simply not found
+static NEXT_RESULT_ID: AtomicU64 = AtomicU64::new(1);
+
+pub(crate) fn new_result_id() -> String {
+    format!("result-{}", NEXT_RESULT_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+// Locked only while holding the session's connection, since releasing a
+// cursor touches the connection
+pub(crate) struct OpenResults<C> {
+    open: HashMap<String, C>,
+    order: VecDeque<String>, // Oldest first
+}
+
+impl<C> Default for OpenResults<C> {
+    fn default() -> Self {
+        OpenResults { open: HashMap::new(), order: VecDeque::new() }
+    }
+}
+
+impl<C> OpenResults<C> {
+    // Returns the cursor evicted to make room, for the caller to release
+    pub(crate) fn insert(&mut self, result_id: String, cursor: C) -> Option<C> {
+        let evicted = if self.order.len() >= MAX_OPEN_RESULTS {
+            self.order.pop_front().and_then(|oldest| self.open.remove(&oldest))
+        } else {
+            None
+        };
+        self.order.push_back(result_id.clone());
+        self.open.insert(result_id, cursor);
+        evicted
+    }
+
+    pub(crate) fn get_mut(&mut self, result_id: &str) -> Result<&mut C, String> {
+        self.open.get_mut(result_id).ok_or_else(|| RESULT_CLOSED.to_string())
+    }
+
+    pub(crate) fn close(&mut self, result_id: &str) -> Option<C> {
+        self.order.retain(|id| id != result_id);
+        self.open.remove(result_id)
+    }
+
+    // Releases every open result, for a statement that can't run beside them
+    pub(crate) fn clear(&mut self) {
+        self.order.clear();
+        self.open.clear();
+    }
+}
@@ -0,0 +1,282 @@
+// Postgres plan analysis from EXPLAIN (FORMAT JSON).
+//
+// The JSON plan is a tree of nodes keyed by their text-format labels ("Node Type",
+// "Relation Name", "Plan Rows", ...). ANALYZE adds "Actual ..." figures per loop and
+// BUFFERS adds block counts; both are optional here, so every figure is optional.
+
+use std::collections::{BTreeMap, HashMap};
+
+use postgres::Client as PgClient;
+use serde_json::Value;
+
+use crate::{pg_values, PlanAnalysis, PlanNode, PlanTableInfo};
+
+// Estimates this far off (either way) from the actual row count are reported
+const MISESTIMATE_FACTOR: f64 = 10.0;
+
+// EXPLAIN ANALYZE runs the statement; it is always rolled back. Inside the user's
+// transaction every EXPLAIN goes through a savepoint, so one that fails doesn't
+// abort the transaction. VERBOSE is only there for each relation's schema
+pub(crate) fn analyze(client: &mut PgClient, sql: &str, run: bool, in_transaction: bool) -> Result<PlanAnalysis, String> {
+    let pg_err = |e: postgres::Error| format!("Explain error: {}", e);
+    let explain = if run { "EXPLAIN (ANALYZE, BUFFERS, VERBOSE, FORMAT JSON) " } else { "EXPLAIN (VERBOSE, FORMAT JSON) " };
+    let (open, close) = match (run, in_transaction) {
+        (_, true) => (Some("SAVEPOINT fluxquery_explain"), Some("ROLLBACK TO SAVEPOINT fluxquery_explain; RELEASE SAVEPOINT fluxquery_explain")),
+        (true, false) => (Some("BEGIN"), Some("ROLLBACK")),
+        (false, false) => (None, None),
+    };
+    if let Some(open) = open {
+        client.batch_execute(open).map_err(pg_err)?;
+    }
+    let explained = client.query(&format!("{}{}", explain, sql), &[]);
+    if let Some(close) = close {
+        client.batch_execute(close).map_err(pg_err)?;
+    }
+    let rows = explained.map_err(pg_err)?;
+    let document = rows.first().and_then(|row| pg_values::row_to_values(row).into_iter().next()).unwrap_or(Value::Null);
+    let Some(root) = document.get(0) else { return Err("Explain error: empty plan".into()) };
+    let plan = root.get("Plan").map(plan_node).ok_or_else(|| "Explain error: empty plan".to_string())?;
+
+    // Rows scanned are counted by ANALYZE, otherwise taken from the statistics
+    let mut analysis = PlanAnalysis { plan: None, steps: Vec::new(), insights: Vec::new(), tables: Vec::new(), rows_scanned_estimate: 0, rows_scanned_exact: run, index_suggestions: None };
+    // table -> (access, rows scanned by its full scans)
+    let mut tables: BTreeMap<String, (&'static str, f64)> = BTreeMap::new();
+    collect(&plan, false, &mut analysis, &mut tables);
+    let mut schemas = HashMap::new();
+    relation_schemas(&root["Plan"], &mut schemas);
+
+    for (table, (access, scanned)) in tables {
+        let total_rows = table_estimate(client, schemas.get(&table).copied(), &table);
+        if access == "SCAN" {
+            // Without ANALYZE a full scan reads the whole table, as far as the statistics know
+            let scanned = if run { Some(scanned) } else { total_rows.map(|t| t as f64) };
+            analysis.rows_scanned_estimate = analysis.rows_scanned_estimate.saturating_add(scanned.unwrap_or(0.0) as u64);
+        }
+        analysis.tables.push(PlanTableInfo { table, access: access.to_string(), total_rows, total_rows_exact: false, access_type: None, key: None, rows_examined: None, filtered_pct: None });
+    }
+    if analysis.insights.is_empty() {
+        analysis.insights.push("Plan analyzed with no obvious full scans".to_string());
+    }
+    if let Some(ms) = root.get("Execution Time").and_then(Value::as_f64) {
+        analysis.insights.push(format!("Execution time: {:.3} ms (rolled back)", ms));
+    }
+    analysis.plan = Some(plan);
+    Ok(analysis)
+}
+
+fn plan_node(node: &Value) -> PlanNode {
+    let text = |key: &str| node.get(key).and_then(Value::as_str).map(str::to_string);
+    let number = |key: &str| node.get(key).and_then(Value::as_f64);
+    let loops = node.get("Actual Loops").and_then(Value::as_u64);
+    let block_count = |kind: &str| {
+        let hit = node.get(format!("Shared {} Blocks", kind).as_str()).and_then(Value::as_u64)?;
+        let local = node.get(format!("Local {} Blocks", kind).as_str()).and_then(Value::as_u64).unwrap_or(0);
+        Some(hit + local)
+    };
+    let operation = text("Node Type").unwrap_or_default();
+    PlanNode {
+        detail: Some(label(node, &operation)),
+        operation,
+        table: text("Relation Name").or_else(|| text("CTE Name")),
+        index: text("Index Name"),
+        startup_cost: number("Startup Cost"),
+        total_cost: number("Total Cost"),
+        estimated_rows: number("Plan Rows"),
+        // Actual rows are averaged per loop, like Plan Rows
+        actual_rows: number("Actual Rows"),
+        actual_time_ms: number("Actual Total Time"),
+        loops,
+        rows_removed: number("Rows Removed by Filter"),
+        blocks_hit: block_count("Hit"),
+        blocks_read: block_count("Read"),
+        flags: Vec::new(),
+        children: node.get("Plans").and_then(Value::as_array).map(|plans| plans.iter().map(plan_node).collect()).unwrap_or_default(),
+    }
+}
+
+// First line of the node in text-format EXPLAIN, e.g. "Index Scan using users_pkey on users u"
+fn label(node: &Value, operation: &str) -> String {
+    let text = |key: &str| node.get(key).and_then(Value::as_str);
+    let mut label = match text("Join Type").filter(|t| *t != "Inner") {
+        Some(join) if operation == "Nested Loop" => format!("Nested Loop {} Join", join),
+        Some(join) if operation.ends_with(" Join") => format!("{} {} Join", operation.trim_end_matches(" Join"), join),
+        _ => operation.to_string(),
+    };
+    if let Some(index) = text("Index Name") {
+        label.push_str(&format!(" using {}", index));
+    }
+    if let Some(relation) = text("Relation Name") {
+        label.push_str(&format!(" on {}", relation));
+        if let Some(alias) = text("Alias").filter(|a| *a != relation) {
+            label.push_str(&format!(" {}", alias));
+        }
+    } else if let Some(cte) = text("CTE Name") {
+        label.push_str(&format!(" on {}", cte));
+    }
+    if let Some(subplan) = text("Subplan Name") {
+        label = format!("{}: {}", subplan, label);
+    }
+    label
+}
+
+// Walks the tree depth-first, filling steps and insights in plan order. Nodes
+// under a Limit stop early, so their actual rows say nothing about the estimate.
+fn collect(node: &PlanNode, under_limit: bool, analysis: &mut PlanAnalysis, tables: &mut BTreeMap<String, (&'static str, f64)>) {
+    let detail = node.detail.clone().unwrap_or_default();
+    let access = match node.operation.as_str() {
+        "Seq Scan" | "Parallel Seq Scan" => Some("SCAN"),
+        "Index Scan" | "Index Only Scan" | "Bitmap Heap Scan" => Some("SEARCH"),
+        _ => None,
+    };
+    if access == Some("SCAN") {
+        analysis.insights.push(format!("Full scan: {}", detail));
+    } else if node.index.is_some() {
+        analysis.insights.push(format!("Index used: {}", detail));
+    }
+    if let (Some(access), Some(table)) = (access, &node.table) {
+        let loops = node.loops.unwrap_or(1) as f64;
+        let scanned = (node.actual_rows.unwrap_or(0.0) + node.rows_removed.unwrap_or(0.0)) * loops;
+        let entry = tables.entry(table.clone()).or_insert((access, 0.0));
+        // A full scan anywhere in the plan outweighs index lookups on the same table
+        if access == "SCAN" {
+            *entry = ("SCAN", entry.1 + scanned);
+        }
+    }
+    let under_limit = under_limit || node.operation == "Limit";
+    if let (Some(estimated), Some(actual), false) = (node.estimated_rows, node.actual_rows, under_limit) {
+        let (low, high) = if estimated < actual { (estimated, actual) } else { (actual, estimated) };
+        if high >= 100.0 && high >= low.max(1.0) * MISESTIMATE_FACTOR {
+            analysis.insights.push(format!("Row estimate off at {}: estimated {}, actual {}", detail, estimated, actual));
+        }
+    }
+    analysis.steps.push(detail);
+    for child in &node.children {
+        collect(child, under_limit, analysis, tables);
+    }
+}
+
+// Schema of each relation in the plan, by name; the first one seen wins
+fn relation_schemas<'a>(node: &'a Value, schemas: &mut HashMap<String, &'a str>) {
+    if let (Some(relation), Some(schema)) = (node.get("Relation Name").and_then(Value::as_str), node.get("Schema").and_then(Value::as_str)) {
+        schemas.entry(relation.to_string()).or_insert(schema);
+    }
+    for child in node.get("Plans").and_then(Value::as_array).into_iter().flatten() {
+        relation_schemas(child, schemas);
+    }
+}
+
+// Row count from the planner statistics; None for tables that were never analyzed.
+// Without a schema the table is looked up on the search path
+fn table_estimate(client: &mut PgClient, schema: Option<&str>, table: &str) -> Option<u64> {
+    let row = client
+        .query_opt(
+            "SELECT c.reltuples::float8 FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE c.relname::text = $2 AND CASE WHEN $1::text IS NULL THEN c.oid = to_regclass(quote_ident($2)) ELSE n.nspname::text = $1 END",
+            &[&schema, &table],
+        )
+        .ok()??;
+    let tuples: f64 = row.get(0);
+    if tuples < 0.0 { None } else { Some(tuples as u64) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Captured EXPLAIN (VERBOSE, FORMAT JSON) output, trimmed to the keys read here
+    const CTE_PLAN: &str = r#"[{"Plan": {
+        "Node Type": "Seq Scan", "Relation Name": "customers", "Schema": "shop", "Alias": "c",
+        "Startup Cost": 9.25, "Total Cost": 12.55, "Plan Rows": 1, "Filter": "(c.id = 2)",
+        "Plans": [
+          {"Node Type": "Seq Scan", "Parent Relationship": "InitPlan", "Subplan Name": "CTE big",
+           "Relation Name": "orders", "Schema": "shop", "Alias": "orders",
+           "Startup Cost": 0.00, "Total Cost": 9.25, "Plan Rows": 100, "Filter": "(orders.total > '400'::numeric)"},
+          {"Node Type": "Aggregate", "Strategy": "Plain", "Parent Relationship": "SubPlan", "Subplan Name": "SubPlan 2",
+           "Startup Cost": 2.25, "Total Cost": 2.26, "Plan Rows": 1,
+           "Plans": [
+             {"Node Type": "CTE Scan", "Parent Relationship": "Outer", "CTE Name": "big", "Alias": "big",
+              "Startup Cost": 0.00, "Total Cost": 2.25, "Plan Rows": 1, "Filter": "(big.customer_id = c.id)"}
+           ]}
+        ]}}]"#;
+
+    // EXPLAIN (ANALYZE, BUFFERS, VERBOSE, FORMAT JSON) of a join under LIMIT 5
+    const LIMIT_PLAN: &str = r#"[{"Plan": {
+        "Node Type": "Limit", "Startup Cost": 0.00, "Total Cost": 0.29, "Plan Rows": 5, "Actual Rows": 5, "Actual Loops": 1,
+        "Actual Total Time": 0.041, "Shared Hit Blocks": 2, "Shared Read Blocks": 0, "Local Hit Blocks": 0, "Local Read Blocks": 0,
+        "Plans": [
+          {"Node Type": "Nested Loop", "Parent Relationship": "Outer", "Join Type": "Inner",
+           "Startup Cost": 0.00, "Total Cost": 28.67, "Plan Rows": 490, "Actual Rows": 5, "Actual Loops": 1,
+           "Join Filter": "(o.customer_id = c.id)", "Rows Removed by Join Filter": 5,
+           "Plans": [
+             {"Node Type": "Seq Scan", "Parent Relationship": "Outer", "Relation Name": "orders", "Schema": "shop", "Alias": "o",
+              "Startup Cost": 0.00, "Total Cost": 9.25, "Plan Rows": 490, "Actual Rows": 5, "Actual Loops": 1,
+              "Filter": "(o.total > '10'::numeric)", "Rows Removed by Filter": 10},
+             {"Node Type": "Materialize", "Parent Relationship": "Inner",
+              "Startup Cost": 0.00, "Total Cost": 1.04, "Plan Rows": 3, "Actual Rows": 2, "Actual Loops": 5,
+              "Plans": [
+                {"Node Type": "Seq Scan", "Parent Relationship": "Outer", "Relation Name": "customers", "Schema": "shop", "Alias": "c",
+                 "Startup Cost": 0.00, "Total Cost": 1.03, "Plan Rows": 3, "Actual Rows": 3, "Actual Loops": 1}
+              ]}
+           ]}
+        ]}, "Execution Time": 0.062}]"#;
+
+    fn collected(json: &str) -> (PlanNode, PlanAnalysis, BTreeMap<String, (&'static str, f64)>) {
+        let document: Value = serde_json::from_str(json).unwrap();
+        let plan = plan_node(&document[0]["Plan"]);
+        let mut analysis = PlanAnalysis { plan: None, steps: Vec::new(), insights: Vec::new(), tables: Vec::new(), rows_scanned_estimate: 0, rows_scanned_exact: false, index_suggestions: None };
+        let mut tables = BTreeMap::new();
+        collect(&plan, false, &mut analysis, &mut tables);
+        (plan, analysis, tables)
+    }
+
+    #[test]
+    fn cte_and_subplan_labels() {
+        let (plan, analysis, tables) = collected(CTE_PLAN);
+        assert_eq!(analysis.steps, ["Seq Scan on customers c", "CTE big: Seq Scan on orders", "SubPlan 2: Aggregate", "CTE Scan on big"]);
+        assert_eq!(analysis.insights, ["Full scan: Seq Scan on customers c", "Full scan: CTE big: Seq Scan on orders"]);
+        assert_eq!(plan.children[1].children[0].table.as_deref(), Some("big"));
+        // The CTE's result is not a table
+        assert_eq!(tables.keys().collect::<Vec<_>>(), ["customers", "orders"]);
+
+        let document: Value = serde_json::from_str(CTE_PLAN).unwrap();
+        let mut schemas = HashMap::new();
+        relation_schemas(&document[0]["Plan"], &mut schemas);
+        assert_eq!(schemas.get("orders"), Some(&"shop"));
+        assert_eq!(schemas.len(), 2);
+    }
+
+    #[test]
+    fn analyzed_nested_loop_under_limit() {
+        let (plan, analysis, tables) = collected(LIMIT_PLAN);
+        assert_eq!((plan.blocks_hit, plan.blocks_read, plan.actual_time_ms), (Some(2), Some(0), Some(0.041)));
+        let join = &plan.children[0];
+        assert_eq!(join.detail.as_deref(), Some("Nested Loop"));
+        assert_eq!(join.children[1].loops, Some(5));
+        // Rows read by a scan are those kept plus those filtered out, over every loop
+        assert_eq!(tables["orders"], ("SCAN", 15.0));
+        assert_eq!(tables["customers"], ("SCAN", 3.0));
+        // 490 estimated against 5 actual is only the LIMIT stopping early
+        assert!(analysis.insights.iter().all(|i| !i.starts_with("Row estimate off")));
+    }
+
+    #[test]
+    fn index_scans_joins_and_misestimates() {
+        let json = r#"[{"Plan": {"Node Type": "Hash Join", "Join Type": "Left", "Plan Rows": 1, "Actual Rows": 400, "Actual Loops": 1,
+            "Plans": [
+              {"Node Type": "Index Scan", "Index Name": "orders_pkey", "Relation Name": "orders", "Alias": "o", "Plan Rows": 400, "Actual Rows": 400, "Actual Loops": 1},
+              {"Node Type": "Hash", "Plans": [{"Node Type": "Seq Scan", "Relation Name": "orders", "Alias": "o2", "Plan Rows": 500, "Actual Rows": 500, "Actual Loops": 1}]}
+            ]}}]"#;
+        let (_, analysis, tables) = collected(json);
+        assert_eq!(analysis.steps, ["Hash Left Join", "Index Scan using orders_pkey on orders o", "Hash", "Seq Scan on orders o2"]);
+        assert_eq!(
+            analysis.insights,
+            [
+                "Row estimate off at Hash Left Join: estimated 1, actual 400",
+                "Index used: Index Scan using orders_pkey on orders o",
+                "Full scan: Seq Scan on orders o2",
+            ]
+        );
+        // The full scan outweighs the index scan on the same table
+        assert_eq!(tables["orders"], ("SCAN", 500.0));
+    }
+}
@@ -0,0 +1,185 @@
+// Postgres schema introspection in the same shape as sqlite_schema. Schemas
+// come from information_schema; relations, columns, constraints and indexes
+// from pg_catalog, which alone knows materialized views, partial and
+// expression indexes.
+
+use std::collections::HashMap;
+
+use postgres::Client as PgClient;
+
+use crate::{DbSchemaSummary, ForeignKeyEdge, TableColumn, TableInfo, TableKey};
+
+// Oids of the relations to describe: every table, view, materialized view and
+// foreign table in `$1` schemas, or only those named `$2`
+const RELATIONS: &str = "SELECT c.oid FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+     WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f') AND n.nspname::text = ANY($1::text[]) \
+     AND ($2::text IS NULL OR c.relname::text = $2::text)";
+
+// Every relation in the user's schemas, or only `table` (in any of them) when given
+pub(crate) fn summary(client: &mut PgClient, table: Option<&str>) -> Result<DbSchemaSummary, String> {
+    let schemas: Vec<String> = client
+        .query(
+            "SELECT schema_name::text FROM information_schema.schemata \
+             WHERE schema_name::text <> 'information_schema' AND schema_name::text NOT LIKE 'pg\\_%' ORDER BY 1",
+            &[],
+        )
+        .map_err(|e| format!("Schemas query error: {}", e))?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+
+    let relations = client
+        .query(
+            &format!(
+                "SELECT c.oid, n.nspname::text, c.relname::text, c.relkind::text \
+                 FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+                 WHERE c.oid IN ({}) ORDER BY n.nspname, c.relname",
+                RELATIONS
+            ),
+            &[&schemas, &table],
+        )
+        .map_err(|e| format!("Tables query error: {}", e))?;
+    let mut tables: Vec<TableInfo> = Vec::new();
+    let mut positions: HashMap<u32, usize> = HashMap::new();
+    for row in relations {
+        let kind = match row.get::<_, String>(3).as_str() {
+            "v" => "VIEW",
+            "m" => "MATERIALIZED_VIEW",
+            "f" => "FOREIGN_TABLE",
+            _ => "TABLE",
+        };
+        positions.insert(row.get(0), tables.len());
+        tables.push(TableInfo { name: row.get(2), columns: Vec::new(), keys: Vec::new(), schema: Some(row.get(1)), kind: kind.into(), engine: None, collation: None, auto_increment: None, module: None, flags: Vec::new() });
+    }
+
+    // columns, in table order
+    let columns = client
+        .query(
+            &format!(
+                "SELECT a.attrelid, a.attname::text, format_type(a.atttypid, a.atttypmod), a.attnotnull, \
+                 pg_get_expr(d.adbin, d.adrelid), \
+                 (SELECT array_position(p.conkey, a.attnum) FROM pg_constraint p WHERE p.conrelid = a.attrelid AND p.contype = 'p'), \
+                 a.attgenerated::text, a.attidentity::text, co.collname::text \
+                 FROM pg_attribute a LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum \
+                 LEFT JOIN pg_collation co ON co.oid = a.attcollation AND co.collname <> 'default' \
+                 WHERE a.attrelid IN ({}) AND a.attnum > 0 AND NOT a.attisdropped ORDER BY a.attrelid, a.attnum",
+                RELATIONS
+            ),
+            &[&schemas, &table],
+        )
+        .map_err(|e| format!("Columns query error: {}", e))?;
+    for row in columns {
+        let Some(&at) = positions.get(&row.get::<_, u32>(0)) else { continue };
+        // A generated column's expression is stored where a default would be;
+        // virtual generated columns arrived in Postgres 18
+        let expression: Option<String> = row.get(4);
+        let pk_ordinal = row.get::<_, Option<i32>>(5).map(|n| n as u32);
+        let (default_value, generated, generation_expression) = match row.get::<_, String>(6).as_str() {
+            "s" => (None, Some("STORED".to_string()), expression),
+            "v" => (None, Some("VIRTUAL".to_string()), expression),
+            _ => (expression, None, None),
+        };
+        let auto_increment = !row.get::<_, String>(7).is_empty() || default_value.as_deref().is_some_and(|d| d.starts_with("nextval("));
+        tables[at].columns.push(TableColumn {
+            name: row.get(1),
+            data_type: row.get(2),
+            not_null: row.get(3),
+            pk: pk_ordinal.is_some(),
+            pk_ordinal,
+            default_value,
+            collation: row.get(8),
+            auto_increment,
+            generated,
+            generation_expression,
+        });
+    }
+
+    // primary, unique, foreign key and check constraints, with foreign key actions; also accumulate global edges
+    let mut foreign_keys: Vec<ForeignKeyEdge> = Vec::new();
+    let constraints = client
+        .query(
+            &format!(
+                "SELECT con.conrelid, con.conname::text, con.contype::text, \
+                 ARRAY(SELECT a.attname::text FROM unnest(con.conkey) WITH ORDINALITY k(attnum, ord) \
+                       JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum ORDER BY k.ord), \
+                 fn.nspname::text, f.relname::text, \
+                 ARRAY(SELECT a.attname::text FROM unnest(con.confkey) WITH ORDINALITY k(attnum, ord) \
+                       JOIN pg_attribute a ON a.attrelid = con.confrelid AND a.attnum = k.attnum ORDER BY k.ord), \
+                 pg_get_constraintdef(con.oid, true), \
+                 CASE con.confupdtype WHEN 'a' THEN 'NO ACTION' WHEN 'r' THEN 'RESTRICT' WHEN 'c' THEN 'CASCADE' WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT' END, \
+                 CASE con.confdeltype WHEN 'a' THEN 'NO ACTION' WHEN 'r' THEN 'RESTRICT' WHEN 'c' THEN 'CASCADE' WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT' END, \
+                 CASE con.confmatchtype WHEN 's' THEN 'SIMPLE' WHEN 'f' THEN 'FULL' WHEN 'p' THEN 'PARTIAL' END, \
+                 con.condeferrable, con.condeferred \
+                 FROM pg_constraint con LEFT JOIN pg_class f ON f.oid = con.confrelid \
+                 LEFT JOIN pg_namespace fn ON fn.oid = f.relnamespace \
+                 WHERE con.conrelid IN ({}) AND con.contype IN ('p', 'u', 'f', 'c') \
+                 ORDER BY con.conrelid, position(con.contype IN 'pufc'), con.conname",
+                RELATIONS
+            ),
+            &[&schemas, &table],
+        )
+        .map_err(|e| format!("Constraints query error: {}", e))?;
+    for row in constraints {
+        let Some(&at) = positions.get(&row.get::<_, u32>(0)) else { continue };
+        let columns: Vec<String> = row.get(3);
+        let mut key = TableKey { name: Some(row.get(1)), columns: columns.clone(), ..Default::default() };
+        match row.get::<_, String>(2).as_str() {
+            "p" => key.key_type = "PRIMARY_KEY".into(),
+            "u" => key.key_type = "UNIQUE".into(),
+            "c" => {
+                key.key_type = "CHECK".into();
+                key.definition = row.get(7);
+            }
+            _ => {
+                let edge = ForeignKeyEdge {
+                    from_table: tables[at].name.clone(),
+                    from_columns: columns,
+                    to_table: row.get(5),
+                    to_columns: row.get(6),
+                    from_schema: tables[at].schema.clone(),
+                    to_schema: row.get(4),
+                    on_update: row.get(8),
+                    on_delete: row.get(9),
+                    match_type: row.get(10),
+                    deferrable: Some(row.get(11)),
+                    initially_deferred: Some(row.get(12)),
+                };
+                key = TableKey::foreign_key(&edge, key.name);
+                foreign_keys.push(edge);
+            }
+        }
+        tables[at].keys.push(key);
+    }
+
+    // indexes not already listed as a primary key or unique constraint; key
+    // columns come back as names, or as the expression for expression indexes
+    let indexes = client
+        .query(
+            &format!(
+                "SELECT i.indrelid, c.relname::text, i.indisunique, \
+                 ARRAY(SELECT pg_get_indexdef(i.indexrelid, k, true) FROM generate_series(1, i.indnkeyatts::int) k ORDER BY k), \
+                 pg_get_expr(i.indpred, i.indrelid, true), pg_get_indexdef(i.indexrelid) \
+                 FROM pg_index i JOIN pg_class c ON c.oid = i.indexrelid \
+                 WHERE i.indrelid IN ({}) \
+                 AND NOT EXISTS (SELECT 1 FROM pg_constraint con WHERE con.conindid = i.indexrelid AND con.contype IN ('p', 'u', 'x')) \
+                 ORDER BY i.indrelid, c.relname",
+                RELATIONS
+            ),
+            &[&schemas, &table],
+        )
+        .map_err(|e| format!("Indexes query error: {}", e))?;
+    for row in indexes {
+        let Some(&at) = positions.get(&row.get::<_, u32>(0)) else { continue };
+        tables[at].keys.push(TableKey {
+            key_type: "INDEX".into(),
+            name: Some(row.get(1)),
+            columns: row.get(3),
+            unique: Some(row.get(2)),
+            predicate: row.get(4),
+            definition: Some(row.get(5)),
+            ..Default::default()
+        });
+    }
+
+    Ok(DbSchemaSummary { tables, foreign_keys, schemas: Some(schemas), triggers: None })
+}
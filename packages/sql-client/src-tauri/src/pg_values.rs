@@ -0,0 +1,497 @@
+// Decoding of Postgres binary (extended protocol) values into JSON by column type.
+//
+// Numbers and booleans stay JSON numbers/booleans, numeric is kept as an exact
+// decimal string, json/jsonb become nested JSON and arrays/ranges/composites are
+// decoded recursively. Types without a decoder here (mostly extension types)
+// fall back to their raw value: a string when it is valid UTF-8, otherwise
+// { "pgType": name, "base64": data }.
+
+use std::error::Error;
+use std::fmt::Write;
+
+use base64::Engine;
+use postgres::fallible_iterator::FallibleIterator;
+use postgres::types::{FromSql, Kind, Type};
+use postgres::Row;
+use postgres_protocol::types as pg;
+use serde_json::{json, Map, Value};
+
+// Raw wire bytes of a column; decoding is driven by the column type instead
+struct RawValue<'a>(Option<&'a [u8]>);
+
+impl<'a> FromSql<'a> for RawValue<'a> {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(RawValue(Some(raw)))
+    }
+
+    fn from_sql_null(_ty: &Type) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(RawValue(None))
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+}
+
+pub(crate) fn row_to_values(row: &Row) -> Vec<Value> {
+    row.columns()
+        .iter()
+        .enumerate()
+        .map(|(idx, col)| match row.try_get::<_, RawValue>(idx) {
+            Ok(RawValue(Some(raw))) => value_to_json(col.type_(), raw),
+            Ok(RawValue(None)) => Value::Null,
+            Err(e) => json!({ "pgType": col.type_().name(), "error": e.to_string() }),
+        })
+        .collect()
+}
+
+pub(crate) fn value_to_json(ty: &Type, raw: &[u8]) -> Value {
+    decode(ty, raw).unwrap_or_else(|_| fallback(ty, raw))
+}
+
+fn fallback(ty: &Type, raw: &[u8]) -> Value {
+    match std::str::from_utf8(raw) {
+        Ok(s) => Value::String(s.to_string()),
+        Err(_) => json!({ "pgType": ty.name(), "base64": base64::engine::general_purpose::STANDARD.encode(raw) }),
+    }
+}
+
+type DecodeResult = Result<Value, Box<dyn Error + Sync + Send>>;
+
+fn decode(ty: &Type, raw: &[u8]) -> DecodeResult {
+    match ty.kind() {
+        Kind::Array(elem) => return decode_array(elem, raw),
+        Kind::Range(elem) => return decode_range(elem, raw),
+        Kind::Domain(inner) => return decode(inner, raw),
+        Kind::Composite(fields) => {
+            let types: Vec<(&str, &Type)> = fields.iter().map(|f| (f.name(), f.type_())).collect();
+            return decode_composite(&types, raw);
+        }
+        Kind::Enum(_) => return Ok(Value::String(pg::text_from_sql(raw)?.to_string())),
+        _ => {}
+    }
+
+    Ok(match *ty {
+        Type::BOOL => Value::Bool(pg::bool_from_sql(raw)?),
+        Type::INT2 => json!(pg::int2_from_sql(raw)?),
+        Type::INT4 => json!(pg::int4_from_sql(raw)?),
+        Type::INT8 => json!(pg::int8_from_sql(raw)?),
+        Type::OID | Type::REGCLASS | Type::REGPROC | Type::REGTYPE | Type::XID | Type::CID => json!(pg::oid_from_sql(raw)?),
+        Type::FLOAT4 => float_to_json(pg::float4_from_sql(raw)? as f64),
+        Type::FLOAT8 => float_to_json(pg::float8_from_sql(raw)?),
+        Type::NUMERIC => Value::String(numeric_to_string(raw)?),
+        // money is an int8 count of cents under the usual two-decimal locale
+        Type::MONEY => {
+            let cents = pg::int8_from_sql(raw)?;
+            Value::String(format!("{}{}.{:02}", if cents < 0 { "-" } else { "" }, (cents / 100).unsigned_abs(), (cents % 100).unsigned_abs()))
+        }
+        Type::CHAR => Value::String(((pg::char_from_sql(raw)? as u8) as char).to_string()),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN | Type::XML => {
+            Value::String(pg::text_from_sql(raw)?.to_string())
+        }
+        Type::JSON => serde_json::from_slice(raw)?,
+        // jsonb carries a one-byte format version before the text
+        Type::JSONB => match raw.split_first() {
+            Some((1, text)) => serde_json::from_slice(text)?,
+            _ => return Err("unsupported jsonb version".into()),
+        },
+        Type::BYTEA => Value::String(base64::engine::general_purpose::STANDARD.encode(pg::bytea_from_sql(raw))),
+        Type::UUID => Value::String(format_uuid(&pg::uuid_from_sql(raw)?)),
+        Type::DATE => Value::String(format_date(pg::date_from_sql(raw)?)),
+        Type::TIME => Value::String(format_time(pg::time_from_sql(raw)?)),
+        Type::TIMETZ => {
+            if raw.len() != 12 {
+                return Err("invalid timetz".into());
+            }
+            let micros = pg::time_from_sql(&raw[..8])?;
+            let west_secs = pg::int4_from_sql(&raw[8..])?;
+            Value::String(format!("{}{}", format_time(micros), format_offset(-west_secs)))
+        }
+        Type::TIMESTAMP => Value::String(format_timestamp(pg::timestamp_from_sql(raw)?)),
+        // timestamptz is sent in UTC regardless of the session time zone
+        Type::TIMESTAMPTZ => {
+            let micros = pg::timestamp_from_sql(raw)?;
+            let ts = format_timestamp(micros);
+            match micros {
+                i64::MAX | i64::MIN => Value::String(ts),
+                _ => match ts.strip_suffix(" BC") {
+                    Some(base) => Value::String(format!("{}+00 BC", base)),
+                    None => Value::String(format!("{}+00", ts)),
+                },
+            }
+        }
+        Type::INTERVAL => Value::String(interval_to_string(raw)?),
+        Type::INET | Type::CIDR => {
+            let inet = pg::inet_from_sql(raw)?;
+            let full = if inet.addr().is_ipv4() { 32 } else { 128 };
+            if *ty == Type::INET && inet.netmask() == full {
+                Value::String(inet.addr().to_string())
+            } else {
+                Value::String(format!("{}/{}", inet.addr(), inet.netmask()))
+            }
+        }
+        Type::MACADDR => {
+            let mac = pg::macaddr_from_sql(raw)?;
+            Value::String(mac.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":"))
+        }
+        Type::BIT | Type::VARBIT => {
+            let bits = pg::varbit_from_sql(raw)?;
+            let mut s = String::with_capacity(bits.len());
+            for i in 0..bits.len() {
+                let byte = bits.bytes()[i / 8];
+                s.push(if byte & (0x80 >> (i % 8)) != 0 { '1' } else { '0' });
+            }
+            Value::String(s)
+        }
+        Type::POINT => {
+            let p = pg::point_from_sql(raw)?;
+            json!({ "x": p.x(), "y": p.y() })
+        }
+        Type::BOX => {
+            let b = pg::box_from_sql(raw)?;
+            json!({
+                "upperRight": { "x": b.upper_right().x(), "y": b.upper_right().y() },
+                "lowerLeft": { "x": b.lower_left().x(), "y": b.lower_left().y() },
+            })
+        }
+        Type::PG_LSN => {
+            let lsn = pg::lsn_from_sql(raw)?;
+            Value::String(format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF))
+        }
+        _ => match ty.name() {
+            "hstore" => {
+                let mut map = Map::new();
+                let mut entries = pg::hstore_from_sql(raw)?;
+                while let Some((k, v)) = entries.next()? {
+                    map.insert(k.to_string(), v.map_or(Value::Null, |v| Value::String(v.to_string())));
+                }
+                Value::Object(map)
+            }
+            "ltree" | "lquery" | "ltxtquery" => Value::String(pg::ltree_from_sql(raw)?.to_string()),
+            _ => fallback(ty, raw),
+        },
+    })
+}
+
+fn float_to_json(f: f64) -> Value {
+    // JSON has no NaN/Infinity; keep them as Postgres spells them
+    if f.is_nan() {
+        Value::String("NaN".into())
+    } else if f.is_infinite() {
+        Value::String(if f > 0.0 { "Infinity" } else { "-Infinity" }.into())
+    } else {
+        json!(f)
+    }
+}
+
+fn decode_array(elem: &Type, raw: &[u8]) -> DecodeResult {
+    let array = pg::array_from_sql(raw)?;
+    let dims: Vec<usize> = array.dimensions().map(|d| Ok(d.len as usize)).collect()?;
+    let values: Vec<Value> = array
+        .values()
+        .map(|v| Ok(v.map_or(Value::Null, |raw| value_to_json(elem, raw))))
+        .collect()?;
+    if dims.is_empty() {
+        return Ok(Value::Array(vec![]));
+    }
+    // Values arrive flattened in row-major order; rebuild the nesting
+    fn nest(dims: &[usize], values: &mut std::vec::IntoIter<Value>) -> Value {
+        match dims.split_first() {
+            Some((len, [])) => Value::Array(values.by_ref().take(*len).collect()),
+            Some((len, rest)) => Value::Array((0..*len).map(|_| nest(rest, values)).collect()),
+            None => Value::Null,
+        }
+    }
+    Ok(nest(&dims, &mut values.into_iter()))
+}
+
+fn decode_range(elem: &Type, raw: &[u8]) -> DecodeResult {
+    let bound = |b: &pg::RangeBound<Option<&[u8]>>| -> (Value, bool) {
+        match b {
+            pg::RangeBound::Inclusive(v) => (v.map_or(Value::Null, |raw| value_to_json(elem, raw)), true),
+            pg::RangeBound::Exclusive(v) => (v.map_or(Value::Null, |raw| value_to_json(elem, raw)), false),
+            pg::RangeBound::Unbounded => (Value::Null, false),
+        }
+    };
+    Ok(match pg::range_from_sql(raw)? {
+        pg::Range::Empty => json!({ "empty": true }),
+        pg::Range::Nonempty(lower, upper) => {
+            let (lower, lower_inclusive) = bound(&lower);
+            let (upper, upper_inclusive) = bound(&upper);
+            json!({ "lower": lower, "upper": upper, "lowerInclusive": lower_inclusive, "upperInclusive": upper_inclusive })
+        }
+    })
+}
+
+// Composite (row) values: field count, then per field its type oid, length and bytes
+fn decode_composite(fields: &[(&str, &Type)], mut raw: &[u8]) -> DecodeResult {
+    let mut take = |n: usize| -> Result<&[u8], Box<dyn Error + Sync + Send>> {
+        if raw.len() < n {
+            return Err("invalid composite value".into());
+        }
+        let (head, tail) = raw.split_at(n);
+        raw = tail;
+        Ok(head)
+    };
+    let count = pg::int4_from_sql(take(4)?)?;
+    if count as usize != fields.len() {
+        return Err("composite field count mismatch".into());
+    }
+    let mut map = Map::new();
+    for (name, ty) in fields {
+        let _oid = take(4)?;
+        let len = pg::int4_from_sql(take(4)?)?;
+        let value = if len < 0 { Value::Null } else { value_to_json(ty, take(len as usize)?) };
+        map.insert(name.to_string(), value);
+    }
+    Ok(Value::Object(map))
+}
+
+// numeric: digit count, weight of the first base-10000 digit, sign, display scale, digits
+fn numeric_to_string(raw: &[u8]) -> Result<String, Box<dyn Error + Sync + Send>> {
+    if raw.len() < 8 {
+        return Err("invalid numeric".into());
+    }
+    let read_i16 = |i: usize| i16::from_be_bytes([raw[i], raw[i + 1]]);
+    let ndigits = read_i16(0).max(0) as usize;
+    let weight = read_i16(2) as i32;
+    let sign = read_i16(4) as u16;
+    let dscale = read_i16(6).max(0) as usize;
+    if raw.len() < 8 + ndigits * 2 {
+        return Err("invalid numeric".into());
+    }
+    match sign {
+        0xC000 => return Ok("NaN".into()),
+        0xD000 => return Ok("Infinity".into()),
+        0xF000 => return Ok("-Infinity".into()),
+        _ => {}
+    }
+    let digit = |i: i32| -> i16 { if i >= 0 && (i as usize) < ndigits { read_i16(8 + i as usize * 2) } else { 0 } };
+
+    let mut out = String::new();
+    if sign == 0x4000 {
+        out.push('-');
+    }
+    if weight < 0 {
+        out.push('0');
+    } else {
+        for i in 0..=weight {
+            if i == 0 {
+                let _ = write!(out, "{}", digit(i));
+            } else {
+                let _ = write!(out, "{:04}", digit(i));
+            }
+        }
+    }
+    if dscale > 0 {
+        let mut frac = String::with_capacity(dscale + 4);
+        let mut i = weight + 1;
+        while frac.len() < dscale {
+            let _ = write!(frac, "{:04}", digit(i));
+            i += 1;
+        }
+        frac.truncate(dscale);
+        out.push('.');
+        out.push_str(&frac);
+    }
+    Ok(out)
+}
+
+// interval: microseconds, days, months; rendered like Postgres' default style
+fn interval_to_string(raw: &[u8]) -> Result<String, Box<dyn Error + Sync + Send>> {
+    if raw.len() != 16 {
+        return Err("invalid interval".into());
+    }
+    let micros = pg::int8_from_sql(&raw[..8])?;
+    let days = pg::int4_from_sql(&raw[8..12])?;
+    let months = pg::int4_from_sql(&raw[12..])?;
+
+    // A field after a negative one carries an explicit "+", and only 1 is singular
+    let mut parts: Vec<String> = Vec::new();
+    let mut after_negative = false;
+    for (n, unit) in [(months / 12, "year"), (months % 12, "mon"), (days, "day")] {
+        if n != 0 {
+            parts.push(format!("{}{} {}{}", if after_negative && n > 0 { "+" } else { "" }, n, unit, if n == 1 { "" } else { "s" }));
+            after_negative = n < 0;
+        }
+    }
+    if micros != 0 || parts.is_empty() {
+        let sign = if micros < 0 { "-" } else if after_negative { "+" } else { "" };
+        let abs = micros.unsigned_abs();
+        let secs = abs / 1_000_000;
+        let frac = abs % 1_000_000;
+        let mut t = format!("{}{:02}:{:02}:{:02}", sign, secs / 3600, (secs / 60) % 60, secs % 60);
+        if frac != 0 {
+            let _ = write!(t, ".{}", format!("{:06}", frac).trim_end_matches('0'));
+        }
+        parts.push(t);
+    }
+    Ok(parts.join(" "))
+}
+
+fn format_uuid(b: &[u8; 16]) -> String {
+    let hex: String = b.iter().map(|x| format!("{:02x}", x)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+// Days since 1970-01-01 to (year, month, day) in the proleptic Gregorian calendar
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+// Postgres dates and timestamps count from 2000-01-01
+const PG_EPOCH_DAYS: i64 = 10_957;
+
+fn format_ymd(days_since_pg_epoch: i64) -> String {
+    let (y, m, d) = civil_from_days(days_since_pg_epoch + PG_EPOCH_DAYS);
+    if y <= 0 {
+        // There is no year 0: 1 BC is year 0 in the proleptic calendar
+        format!("{:04}-{:02}-{:02} BC", 1 - y, m, d)
+    } else {
+        format!("{:04}-{:02}-{:02}", y, m, d)
+    }
+}
+
+fn format_date(days: i32) -> String {
+    match days {
+        i32::MAX => "infinity".into(),
+        i32::MIN => "-infinity".into(),
+        _ => format_ymd(days as i64),
+    }
+}
+
+fn format_time(micros: i64) -> String {
+    let secs = micros.div_euclid(1_000_000);
+    let frac = micros.rem_euclid(1_000_000);
+    let mut s = format!("{:02}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60);
+    if frac != 0 {
+        let _ = write!(s, ".{}", format!("{:06}", frac).trim_end_matches('0'));
+    }
+    s
+}
+
+fn format_timestamp(micros: i64) -> String {
+    match micros {
+        i64::MAX => "infinity".into(),
+        i64::MIN => "-infinity".into(),
+        _ => {
+            let days = micros.div_euclid(86_400_000_000);
+            let time = micros.rem_euclid(86_400_000_000);
+            let date = format_ymd(days);
+            // Keep the BC marker at the end, as Postgres does
+            match date.strip_suffix(" BC") {
+                Some(d) => format!("{} {} BC", d, format_time(time)),
+                None => format!("{} {}", date, format_time(time)),
+            }
+        }
+    }
+}
+
+fn format_offset(east_secs: i32) -> String {
+    let sign = if east_secs < 0 { '-' } else { '+' };
+    let abs = east_secs.unsigned_abs();
+    let (h, m, s) = (abs / 3600, (abs / 60) % 60, abs % 60);
+    match (m, s) {
+        (0, 0) => format!("{}{:02}", sign, h),
+        (_, 0) => format!("{}{:02}:{:02}", sign, h, m),
+        _ => format!("{}{:02}:{:02}:{:02}", sign, h, m, s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fixtures are the hex output of Postgres' *_send functions
+    fn bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn decoded(ty: &Type, hex: &str) -> Value {
+        value_to_json(ty, &bytes(hex))
+    }
+
+    #[test]
+    fn numeric() {
+        let numeric = |hex: &str| numeric_to_string(&bytes(hex)).unwrap();
+        assert_eq!(numeric("0002000040000003000c0d7a"), "-12.345");
+        assert_eq!(numeric("0002ffff00000005000107d0"), "0.00012");
+        assert_eq!(numeric("0001ffff400000011388"), "-0.5");
+        assert_eq!(numeric("0004000200000004000109291a851388"), "123456789.5000");
+        assert_eq!(numeric("00010001000000000001"), "10000");
+        assert_eq!(numeric("00000000c0000000"), "NaN");
+        assert!(numeric_to_string(&bytes("0002000040000003000c")).is_err());
+    }
+
+    #[test]
+    fn money() {
+        assert_eq!(decoded(&Type::MONEY, "ffffffffffffff97"), "-1.05");
+        assert_eq!(decoded(&Type::MONEY, "00000000000004d2"), "12.34");
+    }
+
+    #[test]
+    fn interval() {
+        let interval = |hex: &str| interval_to_string(&bytes(hex)).unwrap();
+        assert_eq!(interval("000000036c9361a0000000030000000e"), "1 year 2 mons 3 days 04:05:06.5");
+        assert_eq!(interval("ffffffff296c5c0000000001fffffff2"), "-1 years -2 mons +1 day -01:00:00");
+        assert_eq!(interval("000000000000000000000001fffffff4"), "-1 years +1 day");
+        assert_eq!(interval("00000001ad274800fffffffd00000000"), "-3 days +02:00:00");
+        assert_eq!(interval("0000000000000000ffffffff00000001"), "1 mon -1 days");
+        assert_eq!(interval("fffffffffffc2f700000000000000000"), "-00:00:00.25");
+        assert_eq!(interval("00000000000000000000000000000000"), "00:00:00");
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(decoded(&Type::TIMESTAMP, "fffffffffff0bdc0"), "1999-12-31 23:59:59");
+        assert_eq!(decoded(&Type::TIMESTAMP, "ff1fc63d1bb12000"), "0001-01-01 00:00:00 BC");
+        assert_eq!(decoded(&Type::TIMESTAMP, "ff1af9e966947290"), "0044-03-15 12:30:00.25 BC");
+        assert_eq!(decoded(&Type::TIMESTAMP, "7fffffffffffffff"), "infinity");
+        assert_eq!(decoded(&Type::TIMESTAMP, "8000000000000000"), "-infinity");
+        assert_eq!(decoded(&Type::TIMESTAMPTZ, "0002b5811750c800"), "2024-02-29 10:00:00+00");
+        assert_eq!(decoded(&Type::TIMESTAMPTZ, "ff1af9e96690a200"), "0044-03-15 12:30:00+00 BC");
+        assert_eq!(decoded(&Type::TIMESTAMPTZ, "7fffffffffffffff"), "infinity");
+    }
+
+    #[test]
+    fn arrays() {
+        assert_eq!(decoded(&Type::INT4_ARRAY, "000000000000000000000017"), json!([]));
+        let nested = "000000020000000100000017000000020000000100000002000000010000000400000001ffffffffffffffff0000000400000004";
+        assert_eq!(decoded(&Type::INT4_ARRAY, nested), json!([[1, null], [null, 4]]));
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(decoded(&Type::INT4_RANGE, "01"), json!({ "empty": true }));
+        assert_eq!(
+            decoded(&Type::INT4_RANGE, "120000000400000001"),
+            json!({ "lower": 1, "upper": null, "lowerInclusive": true, "upperInclusive": false })
+        );
+        assert_eq!(
+            decoded(&Type::INT4_RANGE, "080000000400000006"),
+            json!({ "lower": null, "upper": 6, "lowerInclusive": false, "upperInclusive": false })
+        );
+        assert_eq!(
+            decoded(&Type::INT4_RANGE, "0200000004000000020000000400000007"),
+            json!({ "lower": 2, "upper": 7, "lowerInclusive": true, "upperInclusive": false })
+        );
+    }
+
+    #[test]
+    fn composite_with_null_field() {
+        let raw = bytes("0000000300000017000000040000000100000019ffffffff000000190000000178");
+        let fields = [("id", &Type::INT4), ("note", &Type::TEXT), ("tag", &Type::TEXT)];
+        assert_eq!(decode_composite(&fields, &raw).unwrap(), json!({ "id": 1, "note": null, "tag": "x" }));
+        assert!(decode_composite(&fields[..2], &raw).is_err());
+        assert!(decode_composite(&fields, &raw[..raw.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,383 @@
+// Splitting of SQL scripts into statements, and what each statement returns.
+//
+// The lexer only knows what it takes to find statement boundaries: string
+// literals, quoted identifiers, comments, Postgres dollar quotes and the
+// BEGIN ... END bodies of triggers and routines. Everything else is a word
+// or a punctuation character.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Dialect {
+    Sqlite,
+    Postgres,
+    Mysql,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum StatementKind {
+    Query,  // Plain read query that can be wrapped as a subquery (SELECT, VALUES, WITH ... SELECT)
+    Rows,   // Returns rows but can't be wrapped (PRAGMA, EXPLAIN, SHOW, ... RETURNING)
+    NoRows, // DDL, DML without RETURNING, transaction control, ...
+}
+
+pub(crate) struct Statement {
+    pub(crate) sql: String, // Statement text without the terminating semicolon or surrounding comments
+    pub(crate) kind: StatementKind,
+}
+
+pub(crate) fn split(sql: &str, dialect: Dialect) -> Vec<Statement> {
+    let mut lexer = Lexer { src: sql, pos: 0, dialect };
+    let mut statements = Vec::new();
+    let mut current = StatementScan::default();
+    while let Some((token, start, end)) = lexer.next_token() {
+        if token == Token::Punct(b';') {
+            current.settle_end();
+            if current.block_depth == 0 {
+                statements.extend(std::mem::take(&mut current).finish(sql, dialect));
+                continue;
+            }
+        }
+        current.feed(token, start, end);
+    }
+    current.settle_end();
+    statements.extend(current.finish(sql, dialect));
+    statements
+}
+
+// Tokens of `sql` in order, as slices of it; comments and whitespace are dropped.
+// For callers that look inside a statement rather than split a script
+pub(crate) fn tokens(sql: &str, dialect: Dialect) -> Vec<&str> {
+    let mut lexer = Lexer { src: sql, pos: 0, dialect };
+    let mut tokens = Vec::new();
+    while let Some((_, start, end)) = lexer.next_token() {
+        tokens.push(&sql[start..end]);
+    }
+    tokens
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Punct(u8),
+    Literal, // string, quoted identifier, dollar-quoted body or $n parameter
+}
+
+// Tokens of the statement being scanned, as far as splitting and classifying need them
+#[derive(Default)]
+struct StatementScan<'a> {
+    span: Option<(usize, usize)>,
+    starts_with_paren: bool,
+    paren_depth: usize,
+    top_words: Vec<&'a str>, // Words outside any parentheses, in order
+    top_equals: bool,
+    creates: Option<&'a str>, // Kind of object a CREATE statement makes (TABLE, TRIGGER, ...)
+    block_depth: usize, // Open BEGIN/CASE blocks inside CREATE TRIGGER/PROCEDURE/FUNCTION/EVENT bodies
+    pending_end: bool,  // Saw END; whether it closes a block depends on the next word
+}
+
+// Words naming what a CREATE makes; only the last four have BEGIN ... END bodies
+const CREATED_KINDS: [&str; 10] = ["TABLE", "VIEW", "INDEX", "SCHEMA", "SEQUENCE", "TYPE", "TRIGGER", "PROCEDURE", "FUNCTION", "EVENT"];
+
+impl<'a> StatementScan<'a> {
+    fn feed(&mut self, token: Token<'a>, start: usize, end: usize) {
+        if self.span.is_none() {
+            self.starts_with_paren = token == Token::Punct(b'(');
+        }
+        self.span = Some((self.span.map_or(start, |(s, _)| s), end));
+
+        if let Token::Word(word) = token {
+            // MySQL closes IF/LOOP/WHILE/REPEAT with END IF etc.; those openers aren't
+            // counted. END CASE closes the CASE it follows rather than opening another
+            let after_end = self.pending_end;
+            let closes_other = ["IF", "LOOP", "WHILE", "REPEAT"].iter().any(|k| word.eq_ignore_ascii_case(k));
+            if self.pending_end && !closes_other {
+                self.block_depth = self.block_depth.saturating_sub(1);
+            }
+            self.pending_end = false;
+            // Blocks only open in a routine or trigger body, never inside parentheses
+            // (a column named begin, a CASE in an argument list)
+            if self.paren_depth == 0 && self.has_body() {
+                if word.eq_ignore_ascii_case("BEGIN") || (self.block_depth > 0 && !after_end && word.eq_ignore_ascii_case("CASE")) {
+                    self.block_depth += 1;
+                } else if self.block_depth > 0 && word.eq_ignore_ascii_case("END") {
+                    self.pending_end = true;
+                }
+            }
+        } else {
+            self.settle_end();
+        }
+
+        match token {
+            Token::Punct(b'(') => self.paren_depth += 1,
+            Token::Punct(b')') => self.paren_depth = self.paren_depth.saturating_sub(1),
+            Token::Punct(b'=') if self.paren_depth == 0 => self.top_equals = true,
+            Token::Word(word) if self.paren_depth == 0 => {
+                let in_create = self.top_words.first().is_some_and(|w| w.eq_ignore_ascii_case("CREATE"));
+                if in_create && self.creates.is_none() && CREATED_KINDS.iter().any(|k| word.eq_ignore_ascii_case(k)) {
+                    self.creates = Some(word);
+                }
+                self.top_words.push(word);
+            }
+            _ => {}
+        }
+    }
+
+    fn has_body(&self) -> bool {
+        self.creates.is_some_and(|kind| CREATED_KINDS[6..].iter().any(|k| kind.eq_ignore_ascii_case(k)))
+    }
+
+    fn settle_end(&mut self) {
+        if self.pending_end {
+            self.block_depth = self.block_depth.saturating_sub(1);
+            self.pending_end = false;
+        }
+    }
+
+    fn finish(self, sql: &str, dialect: Dialect) -> Option<Statement> {
+        let (start, end) = self.span?;
+        Some(Statement { sql: sql[start..end].to_string(), kind: self.classify(dialect) })
+    }
+
+    fn classify(&self, dialect: Dialect) -> StatementKind {
+        if self.starts_with_paren {
+            return StatementKind::Query;
+        }
+        let is = |word: &str, keywords: &[&str]| keywords.iter().any(|k| word.eq_ignore_ascii_case(k));
+        let returning = || self.top_words.iter().any(|w| w.eq_ignore_ascii_case("RETURNING"));
+        let dml = |word: &str| is(word, &["INSERT", "UPDATE", "DELETE", "REPLACE", "MERGE"]);
+        let Some(first) = self.top_words.first() else { return StatementKind::NoRows };
+
+        if is(first, &["SELECT", "VALUES", "TABLE"]) {
+            StatementKind::Query
+        } else if is(first, &["WITH"]) {
+            // CTE bodies sit inside parentheses, so the first top-level statement keyword is the main one
+            match self.top_words.iter().skip(1).find(|w| dml(w) || is(w, &["SELECT", "VALUES", "TABLE"])) {
+                Some(main) if dml(main) => if returning() { StatementKind::Rows } else { StatementKind::NoRows },
+                _ => StatementKind::Query,
+            }
+        } else if dml(first) {
+            if returning() { StatementKind::Rows } else { StatementKind::NoRows }
+        } else if is(first, &["EXPLAIN", "SHOW", "FETCH", "CALL"]) || (dialect == Dialect::Mysql && is(first, &["DESCRIBE", "DESC"])) {
+            StatementKind::Rows
+        } else if dialect == Dialect::Sqlite && is(first, &["PRAGMA"]) {
+            // PRAGMA name = value sets; PRAGMA name and PRAGMA name(arg) report
+            if self.top_equals { StatementKind::NoRows } else { StatementKind::Rows }
+        } else {
+            StatementKind::NoRows
+        }
+    }
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    dialect: Dialect,
+}
+
+fn is_word_byte(b: u8) -> bool {
+    // Bytes of multi-byte UTF-8 characters count as identifier characters
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.src.as_bytes().get(self.pos + offset).copied()
+    }
+
+    // Next token with its byte span; comments and whitespace are skipped
+    fn next_token(&mut self) -> Option<(Token<'a>, usize, usize)> {
+        loop {
+            let start = self.pos;
+            let b = self.peek(0)?;
+            let token = match b {
+                _ if b.is_ascii_whitespace() => {
+                    self.pos += 1;
+                    continue;
+                }
+                b'-' if self.peek(1) == Some(b'-') => {
+                    self.skip_line();
+                    continue;
+                }
+                b'#' if self.dialect == Dialect::Mysql => {
+                    self.skip_line();
+                    continue;
+                }
+                b'/' if self.peek(1) == Some(b'*') => {
+                    self.skip_block_comment();
+                    continue;
+                }
+                // MySQL strings take backslash escapes; "..." is a string there too
+                b'\'' => {
+                    self.skip_quoted(b'\'', self.dialect == Dialect::Mysql);
+                    Token::Literal
+                }
+                b'"' => {
+                    self.skip_quoted(b'"', self.dialect == Dialect::Mysql);
+                    Token::Literal
+                }
+                b'`' if self.dialect != Dialect::Postgres => {
+                    self.skip_quoted(b'`', false);
+                    Token::Literal
+                }
+                b'[' if self.dialect == Dialect::Sqlite => {
+                    self.pos = self.src[self.pos..].find(']').map_or(self.src.len(), |i| self.pos + i + 1);
+                    Token::Literal
+                }
+                b'$' if self.dialect == Dialect::Postgres => {
+                    self.skip_dollar();
+                    Token::Literal
+                }
+                _ if is_word_byte(b) => {
+                    while self.peek(0).is_some_and(is_word_byte) {
+                        self.pos += 1;
+                    }
+                    let word = &self.src[start..self.pos];
+                    // Postgres E'...' strings take backslash escapes
+                    if self.dialect == Dialect::Postgres && word.eq_ignore_ascii_case("e") && self.peek(0) == Some(b'\'') {
+                        self.skip_quoted(b'\'', true);
+                        Token::Literal
+                    } else {
+                        Token::Word(word)
+                    }
+                }
+                _ => {
+                    self.pos += 1;
+                    Token::Punct(b)
+                }
+            };
+            return Some((token, start, self.pos));
+        }
+    }
+
+    fn skip_line(&mut self) {
+        self.pos = self.src[self.pos..].find('\n').map_or(self.src.len(), |i| self.pos + i + 1);
+    }
+
+    fn skip_block_comment(&mut self) {
+        // Postgres block comments nest
+        let nests = self.dialect == Dialect::Postgres;
+        let mut depth = 0usize;
+        while let Some(b) = self.peek(0) {
+            if b == b'/' && self.peek(1) == Some(b'*') && (depth == 0 || nests) {
+                depth += 1;
+                self.pos += 2;
+            } else if b == b'*' && self.peek(1) == Some(b'/') {
+                depth -= 1;
+                self.pos += 2;
+                if depth == 0 {
+                    return;
+                }
+            } else {
+                self.pos += 1;
+            }
+        }
+    }
+
+    // Quote at self.pos; a doubled quote is an escaped quote
+    fn skip_quoted(&mut self, quote: u8, backslash_escapes: bool) {
+        self.pos += 1;
+        while let Some(b) = self.peek(0) {
+            if (backslash_escapes && b == b'\\') || (b == quote && self.peek(1) == Some(quote)) {
+                self.pos += 2;
+            } else if b == quote {
+                self.pos += 1;
+                return;
+            } else {
+                self.pos += 1;
+            }
+        }
+        self.pos = self.src.len();
+    }
+
+    // $tag$ ... $tag$ quoting, or a $n parameter
+    fn skip_dollar(&mut self) {
+        let rest = &self.src.as_bytes()[self.pos + 1..];
+        let tag_len = rest.iter().take_while(|b| is_word_byte(**b) && **b != b'$').count();
+        let is_tag = rest.get(tag_len) == Some(&b'$') && !rest.first().is_some_and(u8::is_ascii_digit);
+        if !is_tag {
+            self.pos += 1 + tag_len;
+            return;
+        }
+        let tag = &self.src[self.pos..self.pos + tag_len + 2];
+        let body = self.pos + tag.len();
+        self.pos = self.src[body..].find(tag).map_or(self.src.len(), |i| body + i + tag.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(sql: &str, dialect: Dialect) -> Vec<(String, StatementKind)> {
+        split(sql, dialect).into_iter().map(|s| (s.sql, s.kind)).collect()
+    }
+
+    #[test]
+    fn begin_inside_parentheses_is_a_name() {
+        assert_eq!(
+            parts("CREATE TABLE t(begin int); SELECT 1", Dialect::Sqlite),
+            vec![("CREATE TABLE t(begin int)".to_string(), StatementKind::NoRows), ("SELECT 1".to_string(), StatementKind::Query)],
+        );
+        assert_eq!(split("CREATE VIEW v AS SELECT begin FROM t; SELECT 2", Dialect::Sqlite).len(), 2);
+    }
+
+    #[test]
+    fn trigger_body_is_one_statement() {
+        let sql = "CREATE TRIGGER tr AFTER INSERT ON t BEGIN \
+                   UPDATE t SET n = CASE WHEN new.n > 0 THEN 1 ELSE 0 END; DELETE FROM u; END; SELECT 1";
+        let statements = split(sql, Dialect::Sqlite);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].sql.ends_with("DELETE FROM u; END"));
+    }
+
+    #[test]
+    fn mysql_end_case_closes_its_case() {
+        let sql = "CREATE PROCEDURE p() BEGIN CASE x WHEN 1 THEN SELECT 1; END CASE; IF y THEN SELECT 3; END IF; END; SELECT 2";
+        let statements = split(sql, Dialect::Mysql);
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[1].sql, "SELECT 2");
+    }
+
+    #[test]
+    fn semicolons_in_quotes_and_comments_do_not_split() {
+        assert_eq!(split("SELECT 'a;b', \"c;d\" -- e;f\n; SELECT /* g; */ 2", Dialect::Sqlite).len(), 2);
+        assert_eq!(split("SELECT `a;b`, [c;d]; SELECT 2", Dialect::Sqlite).len(), 2);
+        // MySQL strings take backslash escapes, so \' doesn't end one
+        assert_eq!(split("SELECT 'it\\'s;'; # x;y\nSELECT 2", Dialect::Mysql).len(), 2);
+        assert_eq!(split("SELECT E'it\\'s;', 'a''b;'; SELECT 2", Dialect::Postgres).len(), 2);
+        // Postgres block comments nest
+        assert_eq!(split("SELECT 1 /* a /* b; */ c; */; SELECT 2", Dialect::Postgres).len(), 2);
+    }
+
+    #[test]
+    fn classifies_statements() {
+        let kind = |sql: &str, dialect: Dialect| split(sql, dialect).remove(0).kind;
+        assert_eq!(kind("select 1", Dialect::Sqlite), StatementKind::Query);
+        assert_eq!(kind("(SELECT 1) UNION (SELECT 2)", Dialect::Sqlite), StatementKind::Query);
+        assert_eq!(kind("VALUES (1), (2)", Dialect::Postgres), StatementKind::Query);
+        assert_eq!(kind("WITH c AS (SELECT 1) SELECT * FROM c", Dialect::Sqlite), StatementKind::Query);
+        assert_eq!(kind("WITH c AS (SELECT 1) DELETE FROM t WHERE a IN (SELECT * FROM c)", Dialect::Sqlite), StatementKind::NoRows);
+        assert_eq!(kind("WITH c AS (SELECT 1) UPDATE t SET a = 1 RETURNING a", Dialect::Postgres), StatementKind::Rows);
+        assert_eq!(kind("INSERT INTO t VALUES (1) RETURNING id", Dialect::Sqlite), StatementKind::Rows);
+        assert_eq!(kind("INSERT INTO t SELECT * FROM u", Dialect::Sqlite), StatementKind::NoRows);
+        assert_eq!(kind("PRAGMA table_info(t)", Dialect::Sqlite), StatementKind::Rows);
+        assert_eq!(kind("PRAGMA foreign_keys = ON", Dialect::Sqlite), StatementKind::NoRows);
+        assert_eq!(kind("EXPLAIN SELECT 1", Dialect::Postgres), StatementKind::Rows);
+        assert_eq!(kind("DESCRIBE t", Dialect::Mysql), StatementKind::Rows);
+        assert_eq!(kind("DESCRIBE t", Dialect::Sqlite), StatementKind::NoRows);
+        assert_eq!(kind("CREATE TABLE t(a)", Dialect::Sqlite), StatementKind::NoRows);
+    }
+
+    #[test]
+    fn empty_statements_are_dropped() {
+        assert!(split("  ;; -- nothing\n", Dialect::Sqlite).is_empty());
+        assert_eq!(split("SELECT 1;", Dialect::Sqlite).len(), 1);
+    }
+
+    #[test]
+    fn dollar_quoted_bodies_and_parameters() {
+        let sql = "CREATE FUNCTION f() RETURNS int AS $body$ BEGIN RETURN 1; END; $body$ LANGUAGE plpgsql; SELECT $1, $$a;b$$";
+        let statements = split(sql, Dialect::Postgres);
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[1].sql, "SELECT $1, $$a;b$$");
+    }
+}
@@ -0,0 +1,384 @@
+// Index suggestions for the tables a SQLite query reads in full.
+//
+// The query is only read at the token level. Columns compared in WHERE and
+// JOIN ... ON, and the columns of ORDER BY, are collected per table: equality
+// columns lead the index, then one range column or else the ORDER BY columns.
+// A column counts for a table when qualified with its name or alias, or when
+// unqualified and no other table in FROM has a column of that name. Tables
+// already indexed on those columns are left alone.
+//
+// A suggestion is checked by adding the index to an in-memory copy of the
+// schema (with its sqlite_stat1 statistics) and running EXPLAIN QUERY PLAN again.
+
+use rusqlite::{params, Connection};
+
+use crate::sql_split::{self, Dialect};
+use crate::{sqlite_plan, IndexSuggestion, PlanNode};
+
+// Words that may follow a table name in FROM without being its alias
+const NOT_ALIAS: &[&str] = &[
+    "WHERE", "JOIN", "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "NATURAL", "OUTER", "ON", "USING", "GROUP", "ORDER", "LIMIT", "HAVING",
+    "WINDOW", "UNION", "EXCEPT", "INTERSECT", "INDEXED", "NOT",
+];
+
+// Words that start a clause; column references are read according to the last one
+const CLAUSES: &[&str] = &["SELECT", "FROM", "WHERE", "ON", "USING", "GROUP", "HAVING", "ORDER", "LIMIT", "WINDOW", "RETURNING"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Usage {
+    Equality,
+    Range,
+    Order,
+}
+
+struct ColumnRef {
+    qualifier: Option<String>,
+    column: String,
+    usage: Usage,
+}
+
+// A table named in FROM, with its alias and columns
+struct TableRef {
+    table: String,
+    alias: Option<String>,
+    columns: Vec<String>,
+}
+
+pub(crate) fn suggest(conn: &Connection, sql: &str, plan: &PlanNode, validate: bool) -> Vec<IndexSuggestion> {
+    let tokens = sql_split::tokens(sql, Dialect::Sqlite);
+    let tables: Vec<TableRef> = table_refs(&tokens)
+        .into_iter()
+        .map(|(table, alias)| TableRef { columns: table_columns(conn, &table), table, alias })
+        .collect();
+    let refs = column_refs(&tokens);
+
+    let mut scanned = Vec::new();
+    scanned_tables(plan, &mut scanned);
+    let mut suggestions: Vec<IndexSuggestion> = Vec::new();
+    for name in scanned {
+        // The plan names a table by its alias when it has one
+        let Some(table) = tables.iter().find(|t| t.alias.as_ref().unwrap_or(&t.table).eq_ignore_ascii_case(&name)) else { continue };
+        if table.columns.is_empty() || suggestions.iter().any(|s| s.table == table.table) {
+            continue;
+        }
+        if let Some(suggestion) = table_suggestion(conn, table, &tables, &refs) {
+            suggestions.push(suggestion);
+        }
+    }
+
+    if validate && !suggestions.is_empty() {
+        if let Ok(copy) = schema_copy(conn) {
+            for suggestion in suggestions.iter_mut() {
+                check(&copy, sql, suggestion);
+            }
+        }
+    }
+    suggestions
+}
+
+fn table_suggestion(conn: &Connection, table: &TableRef, tables: &[TableRef], refs: &[ColumnRef]) -> Option<IndexSuggestion> {
+    let canonical = |column: &str| table.columns.iter().find(|c| c.eq_ignore_ascii_case(column)).cloned();
+    let mut equality: Vec<String> = Vec::new();
+    let mut range: Option<String> = None;
+    let mut order: Vec<String> = Vec::new();
+    for r in refs {
+        let belongs = match &r.qualifier {
+            Some(q) => q.eq_ignore_ascii_case(&table.table) || table.alias.as_ref().is_some_and(|a| q.eq_ignore_ascii_case(a)),
+            None => tables.iter().filter(|t| t.columns.iter().any(|c| c.eq_ignore_ascii_case(&r.column))).count() == 1,
+        };
+        let Some(column) = canonical(&r.column).filter(|_| belongs) else { continue };
+        match r.usage {
+            Usage::Equality if !equality.contains(&column) => equality.push(column),
+            Usage::Range if range.is_none() => range = Some(column),
+            Usage::Order if !order.contains(&column) => order.push(column),
+            _ => {}
+        }
+    }
+
+    let mut columns = equality.clone();
+    let mut reasons = Vec::new();
+    if !equality.is_empty() {
+        reasons.push(format!("equality on {}", equality.join(", ")));
+    }
+    match range.filter(|r| !columns.contains(r)) {
+        Some(range) => {
+            reasons.push(format!("range on {}", range));
+            columns.push(range);
+        }
+        // Rows come out of the index already sorted when nothing else narrows them by range
+        None => {
+            let sorted: Vec<String> = order.into_iter().filter(|c| !columns.contains(c)).collect();
+            if !sorted.is_empty() {
+                reasons.push(format!("ORDER BY {}", sorted.join(", ")));
+                columns.extend(sorted);
+            }
+        }
+    }
+    if columns.is_empty() {
+        return None;
+    }
+    let covered = table_indexes(conn, &table.table)
+        .iter()
+        .any(|index| index.len() >= columns.len() && index.iter().zip(&columns).all(|(a, b)| a.eq_ignore_ascii_case(b)));
+    if covered {
+        return None;
+    }
+
+    let quoted: Vec<String> = columns.iter().map(|c| quote(c)).collect();
+    Some(IndexSuggestion {
+        table: table.table.clone(),
+        statement: format!("CREATE INDEX {} ON {} ({})", index_name(&table.table, &columns), quote(&table.table), quoted.join(", ")),
+        columns,
+        reason: format!("Full scan of {}; {}", table.table, reasons.join(", ")),
+        validated: None,
+        plan_with_index: None,
+    })
+}
+
+// Tables the plan reads in full, by the name the plan gives them
+fn scanned_tables(node: &PlanNode, tables: &mut Vec<String>) {
+    if let ("SCAN", Some(table)) = (node.operation.as_str(), &node.table) {
+        if !tables.contains(table) {
+            tables.push(table.clone());
+        }
+    }
+    for child in &node.children {
+        scanned_tables(child, tables);
+    }
+}
+
+// Tables named after FROM, JOIN or a comma in FROM, with their aliases.
+// Subqueries in FROM are skipped; the tables inside them are picked up on their own
+pub(crate) fn table_refs(tokens: &[&str]) -> Vec<(String, Option<String>)> {
+    let mut refs = Vec::new();
+    let mut in_from = false;
+    for (i, token) in tokens.iter().enumerate() {
+        let upper = token.to_ascii_uppercase();
+        let starts_ref = match upper.as_str() {
+            "FROM" | "JOIN" => {
+                in_from = true;
+                true
+            }
+            "," => in_from,
+            _ if CLAUSES.contains(&upper.as_str()) => {
+                in_from = false;
+                false
+            }
+            _ => false,
+        };
+        if !starts_ref {
+            continue;
+        }
+        let mut j = i + 1;
+        let Some(mut table) = tokens.get(j).and_then(|t| identifier(t)) else { continue };
+        // schema.table
+        if tokens.get(j + 1) == Some(&".") {
+            let Some(name) = tokens.get(j + 2).and_then(|t| identifier(t)) else { continue };
+            table = name;
+            j += 2;
+        }
+        j += 1;
+        if tokens.get(j).is_some_and(|t| t.eq_ignore_ascii_case("AS")) {
+            j += 1;
+        }
+        let alias = tokens
+            .get(j)
+            .filter(|t| !NOT_ALIAS.contains(&t.to_ascii_uppercase().as_str()))
+            .and_then(|t| identifier(t));
+        refs.push((table, alias));
+    }
+    refs
+}
+
+// Table behind a name a plan uses, which is the alias when the query gave one
+pub(crate) fn aliased_table<'a>(refs: &'a [(String, Option<String>)], named: &str) -> Option<&'a String> {
+    refs.iter().find(|(table, alias)| alias.as_ref().unwrap_or(table).eq_ignore_ascii_case(named)).map(|(table, _)| table)
+}
+
+// Columns compared in WHERE and ON, and columns of ORDER BY
+fn column_refs(tokens: &[&str]) -> Vec<ColumnRef> {
+    let token = |i: usize| tokens.get(i).copied().unwrap_or("");
+    let is = |i: usize, word: &str| token(i).eq_ignore_ascii_case(word);
+    // Clause of each enclosing parenthesis, restored when it closes
+    let mut clause = String::new();
+    let mut outer: Vec<String> = Vec::new();
+    let mut refs = Vec::new();
+    for i in 0..tokens.len() {
+        let upper = token(i).to_ascii_uppercase();
+        match upper.as_str() {
+            "(" => outer.push(clause.clone()),
+            ")" => clause = outer.pop().unwrap_or_default(),
+            _ if CLAUSES.contains(&upper.as_str()) => {
+                clause = upper;
+                continue;
+            }
+            _ => {}
+        }
+        // Function names and the column half of qualifier.column are not references
+        if (i > 0 && token(i - 1) == ".") || token(i + 1) == "(" {
+            continue;
+        }
+        let Some(name) = identifier(token(i)) else { continue };
+        let (qualifier, column, last) = match (token(i + 1), identifier(token(i + 2))) {
+            (".", Some(column)) => (Some(name), column, i + 2),
+            _ => (None, name, i),
+        };
+        let usage = match clause.as_str() {
+            "WHERE" | "ON" => {
+                let after = token(last + 1);
+                let before = if i > 0 { token(i - 1) } else { "" };
+                let before_op = if i > 1 { token(i - 2) } else { "" };
+                if after == "=" || (is(last + 1, "IS") && !is(last + 2, "NOT")) || is(last + 1, "IN") {
+                    Some(Usage::Equality)
+                } else if (after == "<" && token(last + 2) != ">") || after == ">" || ["BETWEEN", "LIKE", "GLOB"].iter().any(|w| is(last + 1, w)) {
+                    Some(Usage::Range)
+                } else if before == "=" && !["<", ">", "!"].contains(&before_op) {
+                    Some(Usage::Equality)
+                } else if before == "<" || before == ">" || (before == "=" && (before_op == "<" || before_op == ">")) {
+                    Some(Usage::Range)
+                } else {
+                    None
+                }
+            }
+            "ORDER" => Some(Usage::Order),
+            _ => None,
+        };
+        if let Some(usage) = usage {
+            refs.push(ColumnRef { qualifier, column, usage });
+        }
+    }
+    refs
+}
+
+// Name in an identifier token, unquoted; None for literals, numbers and punctuation
+pub(crate) fn identifier(token: &str) -> Option<String> {
+    let first = token.chars().next()?;
+    match first {
+        '"' | '`' => {
+            let inner = token.get(1..token.len() - 1).unwrap_or("");
+            Some(inner.replace(&first.to_string().repeat(2), &first.to_string()))
+        }
+        '[' => Some(token.trim_start_matches('[').trim_end_matches(']').to_string()),
+        _ if first.is_alphabetic() || first == '_' => Some(token.to_string()),
+        _ => None,
+    }
+}
+
+// idx_<table>_<columns>, kept to identifier characters so plan details show it whole
+fn index_name(table: &str, columns: &[String]) -> String {
+    let name = format!("idx_{}_{}", table, columns.join("_"));
+    name.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect()
+}
+
+pub(crate) fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// Columns of a table; empty for views, CTEs and anything else that can't be indexed
+fn table_columns(conn: &Connection, table: &str) -> Vec<String> {
+    let is_table = conn
+        .query_row("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1 COLLATE NOCASE", params![table], |_| Ok(()))
+        .is_ok();
+    if !is_table {
+        return Vec::new();
+    }
+    let Ok(mut stmt) = conn.prepare(&format!("PRAGMA table_info({})", quote(table))) else { return Vec::new() };
+    stmt.query_map([], |row| row.get::<_, String>(1)).map(|rows| rows.flatten().collect()).unwrap_or_default()
+}
+
+// Column lists of the table's indexes, in index order; expression columns have no name
+fn table_indexes(conn: &Connection, table: &str) -> Vec<Vec<String>> {
+    let Ok(mut list) = conn.prepare(&format!("PRAGMA index_list({})", quote(table))) else { return Vec::new() };
+    let names: Vec<String> = list.query_map([], |row| row.get::<_, String>(1)).map(|rows| rows.flatten().collect()).unwrap_or_default();
+    names
+        .iter()
+        .filter_map(|index| {
+            let mut info = conn.prepare(&format!("PRAGMA index_info({})", quote(index))).ok()?;
+            let columns = info.query_map([], |row| row.get::<_, Option<String>>(2)).ok()?.flatten();
+            Some(columns.map(Option::unwrap_or_default).collect())
+        })
+        .collect()
+}
+
+// Tables, views, indexes and triggers without their rows, plus the planner statistics
+fn schema_copy(conn: &Connection) -> Result<Connection, String> {
+    let copy = Connection::open_in_memory().map_err(|e| format!("Schema copy error: {}", e))?;
+    let mut stmt = conn
+        .prepare("SELECT sql FROM sqlite_master WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%' ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'view' THEN 1 ELSE 2 END")
+        .map_err(|e| format!("Schema copy error: {}", e))?;
+    let statements: Vec<String> = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| format!("Schema copy error: {}", e))?
+        .flatten()
+        .collect();
+    for statement in statements {
+        // Virtual tables whose module is not loaded here are left out
+        let _ = copy.execute_batch(&statement);
+    }
+
+    let stats: Vec<(String, Option<String>, String)> = conn
+        .prepare("SELECT tbl, idx, stat FROM sqlite_stat1")
+        .and_then(|mut stmt| stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?.collect())
+        .unwrap_or_default();
+    if !stats.is_empty() {
+        // ANALYZE creates sqlite_stat1; analyzing sqlite_master then reloads it
+        copy.execute_batch("ANALYZE sqlite_master; DELETE FROM sqlite_stat1").map_err(|e| format!("Schema copy error: {}", e))?;
+        for (tbl, idx, stat) in stats {
+            let _ = copy.execute("INSERT INTO sqlite_stat1 (tbl, idx, stat) VALUES (?1, ?2, ?3)", params![tbl, idx, stat]);
+        }
+        copy.execute_batch("ANALYZE sqlite_master").map_err(|e| format!("Schema copy error: {}", e))?;
+    }
+    Ok(copy)
+}
+
+// Adds the index to the copy, reads the plan, and drops it again for the next suggestion
+fn check(copy: &Connection, sql: &str, suggestion: &mut IndexSuggestion) {
+    if copy.execute_batch(&suggestion.statement).is_err() {
+        return;
+    }
+    if let Ok((plan, steps)) = sqlite_plan::explain(copy, sql) {
+        suggestion.validated = Some(uses_index(&plan, &index_name(&suggestion.table, &suggestion.columns)));
+        suggestion.plan_with_index = Some(steps);
+    }
+    let _ = copy.execute_batch(&format!("DROP INDEX {}", index_name(&suggestion.table, &suggestion.columns)));
+}
+
+fn uses_index(node: &PlanNode, name: &str) -> bool {
+    node.index.as_deref().is_some_and(|index| index.eq_ignore_ascii_case(name)) || node.children.iter().any(|child| uses_index(child, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite_plan;
+
+    fn suggestions(sql: &str) -> Vec<IndexSuggestion> {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE event(id INTEGER PRIMARY KEY, kind TEXT, user_id INT, at REAL); CREATE INDEX event_kind ON event(kind);").unwrap();
+        let (plan, _) = sqlite_plan::explain(&conn, sql).unwrap();
+        suggest(&conn, sql, &plan, true)
+    }
+
+    #[test]
+    fn equality_before_range() {
+        let found = suggestions("SELECT * FROM event WHERE at > 1 AND user_id = 3");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].table, "event");
+        assert_eq!(found[0].columns, ["user_id", "at"]);
+        assert_eq!(found[0].statement, "CREATE INDEX idx_event_user_id_at ON \"event\" (\"user_id\", \"at\")");
+        assert_eq!(found[0].validated, Some(true));
+    }
+
+    #[test]
+    fn alias_qualified_columns() {
+        let found = suggestions("SELECT * FROM event AS e WHERE e.user_id = 3");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].columns, ["user_id"]);
+    }
+
+    #[test]
+    fn nothing_for_an_indexed_search() {
+        assert!(suggestions("SELECT * FROM event WHERE kind = 'a'").is_empty());
+        assert!(suggestions("SELECT * FROM event").is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+// SQLite results read page by page from a statement kept open between commands.
+//
+// Each page is stepped out of the same prepared statement, skipping the rows
+// before it without decoding them, and only the last page read is kept. Going
+// back before the statement's position re-runs it from the start. An open
+// statement pins the connection's read snapshot (and, outside WAL mode, a shared
+// lock on the file), so it is finalized as soon as its last row is read and a
+// session keeps only a few results open.
+
+use std::ffi::{c_int, CStr, CString};
+use std::ptr::null_mut;
+
+use rusqlite::{ffi, types::ValueRef, Connection};
+
+use crate::{sqlite_column_info, value_ref_to_json, ColumnInfo};
+
+pub(crate) struct SqliteCursor {
+    stmt: *mut ffi::sqlite3_stmt, // Null once every row has been read
+    pub(crate) sql: String,
+    pub(crate) columns: Vec<String>,
+    pub(crate) column_info: Vec<ColumnInfo>,
+    position: u64,                                  // Rows stepped since the statement last started
+    last_page: (u64, Vec<Vec<serde_json::Value>>), // Offset and rows of the last page read
+    pub(crate) total_rows: Option<u64>,             // Known once exhausted or counted
+}
+
+// SAFETY: the statement is only stepped or finalized while holding the lock of
+// the connection it was prepared on (see OpenResults)
+unsafe impl Send for SqliteCursor {}
+
+impl SqliteCursor {
+    pub(crate) fn open(conn: &Connection, sql: &str) -> Result<SqliteCursor, String> {
+        let stmt = prepare(conn, sql)?;
+        // SAFETY: stmt is a valid prepared statement; names are copied before the next call
+        let columns = unsafe {
+            (0..ffi::sqlite3_column_count(stmt))
+                .map(|i| {
+                    let name = ffi::sqlite3_column_name(stmt, i);
+                    if name.is_null() { String::new() } else { CStr::from_ptr(name).to_string_lossy().into_owned() }
+                })
+                .collect()
+        };
+        Ok(SqliteCursor {
+            stmt,
+            sql: sql.to_string(),
+            columns,
+            column_info: sqlite_column_info(conn, sql),
+            position: 0,
+            last_page: (0, Vec::new()),
+            total_rows: None,
+        })
+    }
+
+    // Rows of `page`, stepping forward to it or starting the statement over
+    pub(crate) fn page(&mut self, conn: &Connection, page: u32, page_size: u32) -> Result<Vec<Vec<serde_json::Value>>, String> {
+        if page_size == 0 {
+            return Err("pageSize must be at least 1".to_string());
+        }
+        let start = (page as u64).saturating_mul(page_size as u64);
+        let (last_start, last_rows) = &self.last_page;
+        let last_complete = last_rows.len() == page_size as usize || self.total_rows == Some(last_start + last_rows.len() as u64);
+        if *last_start == start && last_complete {
+            return Ok(last_rows.clone());
+        }
+        if self.total_rows.is_some_and(|total| start >= total) {
+            return Ok(Vec::new());
+        }
+        if start < self.position || self.stmt.is_null() {
+            self.restart(conn)?;
+        }
+        let mut rows = Vec::new();
+        while rows.len() < page_size as usize {
+            let skip = self.position < start;
+            match self.step(conn, skip)? {
+                Some(row) if !skip => rows.push(row),
+                Some(_) => {}
+                None => break,
+            }
+        }
+        self.last_page = (start, rows.clone());
+        Ok(rows)
+    }
+
+    // Total row count; counted with a COUNT(*) wrapper unless every row has been read
+    pub(crate) fn count(&mut self, conn: &Connection) -> Result<u64, String> {
+        if let Some(total) = self.total_rows {
+            return Ok(total);
+        }
+        let count_sql = format!("SELECT COUNT(*) AS count FROM ( {} )", self.sql);
+        let total = conn
+            .query_row(&count_sql, [], |r| r.get::<_, i64>(0))
+            .map(|v| v as u64)
+            .map_err(|e| format!("Count error: {}", e))?;
+        self.total_rows = Some(total);
+        Ok(total)
+    }
+
+    // Rewinds the statement, or prepares it again once it has been finalized
+    fn restart(&mut self, conn: &Connection) -> Result<(), String> {
+        if self.stmt.is_null() {
+            self.stmt = prepare(conn, &self.sql)?;
+        } else {
+            // SAFETY: the caller holds the connection lock
+            unsafe { ffi::sqlite3_reset(self.stmt) };
+        }
+        self.position = 0;
+        Ok(())
+    }
+
+    // Next row, decoded unless skipped; None once the statement is done
+    fn step(&mut self, conn: &Connection, skip: bool) -> Result<Option<Vec<serde_json::Value>>, String> {
+        // SAFETY: the caller holds the connection lock; column values are
+        // copied out before the next step invalidates them
+        unsafe {
+            match ffi::sqlite3_step(self.stmt) {
+                ffi::SQLITE_ROW => {
+                    self.position += 1;
+                    let values = if skip { Vec::new() } else { (0..self.columns.len() as c_int).map(|i| value_ref_to_json(column_value(self.stmt, i))).collect() };
+                    Ok(Some(values))
+                }
+                ffi::SQLITE_DONE => {
+                    self.finalize();
+                    self.total_rows = Some(self.position);
+                    Ok(None)
+                }
+                _ => {
+                    let message = error_message(conn);
+                    self.finalize();
+                    Err(format!("Query error: {}", message))
+                }
+            }
+        }
+    }
+
+    fn finalize(&mut self) {
+        // SAFETY: the statement is finalized once and the pointer cleared
+        unsafe { ffi::sqlite3_finalize(self.stmt) };
+        self.stmt = null_mut();
+    }
+}
+
+impl Drop for SqliteCursor {
+    fn drop(&mut self) {
+        self.finalize();
+    }
+}
+
+fn prepare(conn: &Connection, sql: &str) -> Result<*mut ffi::sqlite3_stmt, String> {
+    let c_sql = CString::new(sql).map_err(|e| format!("Prepare error: {}", e))?;
+    let mut stmt: *mut ffi::sqlite3_stmt = null_mut();
+    // SAFETY: the caller holds the connection lock
+    let rc = unsafe { ffi::sqlite3_prepare_v2(conn.handle(), c_sql.as_ptr(), -1, &mut stmt, null_mut()) };
+    if rc != ffi::SQLITE_OK || stmt.is_null() {
+        let message = if rc == ffi::SQLITE_OK { "empty statement".to_string() } else { error_message(conn) };
+        // SAFETY: finalizing a null statement is a no-op
+        unsafe { ffi::sqlite3_finalize(stmt) };
+        return Err(format!("Prepare error: {}", message));
+    }
+    Ok(stmt)
+}
+
+// Value of column `i` of the row `stmt` is positioned on.
+// SAFETY: the returned slices are only valid until the next step or finalize
+unsafe fn column_value<'a>(stmt: *mut ffi::sqlite3_stmt, i: c_int) -> ValueRef<'a> {
+    match ffi::sqlite3_column_type(stmt, i) {
+        ffi::SQLITE_INTEGER => ValueRef::Integer(ffi::sqlite3_column_int64(stmt, i)),
+        ffi::SQLITE_FLOAT => ValueRef::Real(ffi::sqlite3_column_double(stmt, i)),
+        ffi::SQLITE_TEXT => ValueRef::Text(column_bytes(stmt, i, ffi::sqlite3_column_text(stmt, i))),
+        ffi::SQLITE_BLOB => ValueRef::Blob(column_bytes(stmt, i, ffi::sqlite3_column_blob(stmt, i) as *const u8)),
+        _ => ValueRef::Null,
+    }
+}
+
+// Byte length is read after the text/blob pointer, as the SQLite docs require
+unsafe fn column_bytes<'a>(stmt: *mut ffi::sqlite3_stmt, i: c_int, data: *const u8) -> &'a [u8] {
+    if data.is_null() {
+        return &[];
+    }
+    std::slice::from_raw_parts(data, ffi::sqlite3_column_bytes(stmt, i) as usize)
+}
+
+fn error_message(conn: &Connection) -> String {
+    // SAFETY: sqlite3_errmsg returns a NUL-terminated string owned by the connection
+    unsafe { CStr::from_ptr(ffi::sqlite3_errmsg(conn.handle())) }.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor(conn: &Connection) -> SqliteCursor {
+        conn.execute_batch("CREATE TABLE n(v INT); WITH RECURSIVE s(v) AS (SELECT 1 UNION ALL SELECT v + 1 FROM s WHERE v < 5) INSERT INTO n SELECT v FROM s;").unwrap();
+        SqliteCursor::open(conn, "SELECT v FROM n ORDER BY v").unwrap()
+    }
+
+    fn firsts(rows: Vec<Vec<serde_json::Value>>) -> Vec<i64> {
+        rows.iter().map(|row| row[0].as_i64().unwrap()).collect()
+    }
+
+    #[test]
+    fn pages_forward_and_back() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut cursor = cursor(&conn);
+        assert_eq!(firsts(cursor.page(&conn, 1, 2).unwrap()), [3, 4]);
+        assert_eq!(cursor.total_rows, None);
+        assert_eq!(firsts(cursor.page(&conn, 0, 2).unwrap()), [1, 2]);
+        assert_eq!(firsts(cursor.page(&conn, 2, 2).unwrap()), [5]);
+        assert_eq!(cursor.total_rows, Some(5));
+        assert!(cursor.stmt.is_null());
+        // Finalized once exhausted, so going back prepares the statement again
+        assert_eq!(firsts(cursor.page(&conn, 1, 2).unwrap()), [3, 4]);
+        assert!(cursor.page(&conn, 3, 2).unwrap().is_empty());
+        assert_eq!(cursor.last_page.1.len(), 2);
+    }
+
+    #[test]
+    fn rejects_an_empty_page_size() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut cursor = cursor(&conn);
+        assert_eq!(cursor.page(&conn, 0, 0).unwrap_err(), "pageSize must be at least 1");
+        assert_eq!(cursor.count(&conn).unwrap(), 5);
+    }
+}
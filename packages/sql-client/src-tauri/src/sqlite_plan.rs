@@ -0,0 +1,267 @@
+// SQLite plan tree from EXPLAIN QUERY PLAN.
+//
+// Each row is (id, parent, notused, detail); a row's parent is the id of an
+// earlier row, or 0 at the top level. The detail is all there is to go on, e.g.
+//   SEARCH u USING COVERING INDEX u_name (name=?)
+//   CORRELATED SCALAR SUBQUERY 1
+//   MATERIALIZE c
+//   USE TEMP B-TREE FOR ORDER BY
+
+use rusqlite::Connection;
+
+use crate::sql_split::{self, Dialect};
+use crate::{sqlite_advisor, PlanAnalysis, PlanNode, PlanTableInfo};
+
+// Plan tree, steps, insights, table sizes and index suggestions for a lone query.
+// Table sizes are estimates unless `exact_counts` asks for COUNT(*)
+pub(crate) fn analyze(conn: &Connection, sql: &str, exact_counts: bool) -> Result<PlanAnalysis, String> {
+    let (plan, steps) = explain(conn, sql)?;
+    let mut insights: Vec<String> = Vec::new();
+    for step in &steps {
+        let upper = step.to_uppercase();
+        if upper.contains("USING INDEX") {
+            insights.push(format!("Index used: {}", step));
+        }
+        if upper.contains("SCAN ") && !upper.contains("SEARCH ") {
+            insights.push(format!("Full scan: {}", step));
+        }
+    }
+    insights.extend(tree_insights(&plan));
+    if insights.is_empty() && !steps.is_empty() {
+        insights.push("Plan analyzed with no obvious full scans".to_string());
+    }
+
+    // Tables in plan order; a full scan anywhere outweighs lookups on the same table
+    let refs = sqlite_advisor::table_refs(&sql_split::tokens(sql, Dialect::Sqlite));
+    let mut accessed: Vec<(String, &'static str)> = Vec::new();
+    table_access(&plan, &refs, &mut accessed);
+    let mut analysis = PlanAnalysis { plan: None, steps, insights, tables: Vec::new(), rows_scanned_estimate: 0, rows_scanned_exact: true, index_suggestions: None };
+    for (table, access) in accessed {
+        let (total_rows, exact) = table_rows(conn, &table, exact_counts);
+        if access == "SCAN" {
+            analysis.rows_scanned_estimate = analysis.rows_scanned_estimate.saturating_add(total_rows.unwrap_or(0));
+            analysis.rows_scanned_exact &= exact;
+        }
+        analysis.tables.push(PlanTableInfo { table, access: access.to_string(), total_rows, total_rows_exact: exact, access_type: None, key: None, rows_examined: None, filtered_pct: None });
+    }
+    analysis.index_suggestions = Some(sqlite_advisor::suggest(conn, sql, &plan, false));
+    analysis.plan = Some(plan);
+    Ok(analysis)
+}
+
+// Tables read by SCAN and SEARCH steps, leaving out subquery results and SQLite's own tables.
+// The plan names a table by its alias when it has one; `refs` maps it back
+fn table_access(node: &PlanNode, refs: &[(String, Option<String>)], tables: &mut Vec<(String, &'static str)>) {
+    let access = match node.operation.as_str() {
+        "SCAN" => Some("SCAN"),
+        "SEARCH" => Some("SEARCH"),
+        _ => None,
+    };
+    if let (Some(access), Some(named)) = (access, &node.table) {
+        let table = sqlite_advisor::aliased_table(refs, named).unwrap_or(named);
+        if !table.starts_with('(') && !table.starts_with("sqlite_") {
+            match tables.iter_mut().find(|(name, _)| name == table) {
+                Some(entry) if access == "SCAN" => entry.1 = "SCAN",
+                Some(_) => {}
+                None => tables.push((table.clone(), access)),
+            }
+        }
+    }
+    for child in &node.children {
+        table_access(child, refs, tables);
+    }
+}
+
+// The tree under a "QUERY PLAN" root, and every detail in plan order
+pub(crate) fn explain(conn: &Connection, sql: &str) -> Result<(PlanNode, Vec<String>), String> {
+    let mut stmt = conn
+        .prepare(&format!("EXPLAIN QUERY PLAN {}", sql))
+        .map_err(|e| format!("Explain prepare error: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(3)?)))
+        .map_err(|e| format!("Explain query error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Explain row error: {}", e))?;
+
+    let steps = rows.iter().map(|(_, _, detail)| detail.clone()).collect();
+    // Rows whose parent is missing are kept, at the top level
+    let ids: Vec<i64> = rows.iter().map(|(id, _, _)| *id).collect();
+    let top = |parent: i64| parent == 0 || !ids.contains(&parent);
+    let mut root = plan_node("QUERY PLAN".to_string());
+    root.children = rows.iter().filter(|(_, parent, _)| top(*parent)).map(|row| subtree(row, &rows)).collect();
+    Ok((root, steps))
+}
+
+fn subtree((id, _, detail): &(i64, i64, String), rows: &[(i64, i64, String)]) -> PlanNode {
+    let mut node = step_node(detail);
+    node.children = rows.iter().filter(|(_, parent, _)| parent == id).map(|row| subtree(row, rows)).collect();
+    node
+}
+
+fn step_node(detail: &str) -> PlanNode {
+    // ASCII only, so byte offsets into `upper` hold in `detail`
+    let upper = detail.to_ascii_uppercase();
+    let words: Vec<&str> = detail.split_whitespace().collect();
+    // Subqueries, compound parts and OR terms are numbered; the number says nothing here
+    let unnumbered = match words.split_last() {
+        Some((last, rest)) if !rest.is_empty() && last.chars().all(|c| c.is_ascii_digit()) => rest.join(" "),
+        _ => detail.to_string(),
+    };
+    let first = words.first().map(|w| w.to_ascii_uppercase()).unwrap_or_default();
+    let (operation, table) = match first.as_str() {
+        "SCAN" | "SEARCH" => (first.clone(), words.get(1).filter(|_| !upper.starts_with("SCAN CONSTANT ROW"))),
+        "MATERIALIZE" | "CO-ROUTINE" => (first.clone(), words.get(1)),
+        "USE" if upper.starts_with("USE TEMP B-TREE") => ("USE TEMP B-TREE".to_string(), None),
+        "BLOOM" => ("BLOOM FILTER".to_string(), words.get(3)),
+        _ => (unnumbered, None),
+    };
+
+    let mut node = plan_node(operation);
+    node.table = table.map(|t| t.to_string());
+    // "USING [AUTOMATIC] [PARTIAL] [COVERING] INDEX name"; automatic indexes have no name
+    node.index = if upper.contains("USING INTEGER PRIMARY KEY") {
+        Some("INTEGER PRIMARY KEY".to_string())
+    } else {
+        upper
+            .find(" INDEX ")
+            .filter(|i| upper[..*i].contains(" USING") && !upper.contains("AUTOMATIC"))
+            .and_then(|i| detail[i + 7..].split_whitespace().next())
+            .map(str::to_string)
+    };
+    let flag = |present: bool, name: &str| present.then(|| name.to_string());
+    node.flags = [
+        flag(upper.contains("COVERING INDEX"), "COVERING INDEX"),
+        flag(upper.contains("AUTOMATIC"), "AUTOMATIC INDEX"),
+        flag(upper.starts_with("CORRELATED "), "CORRELATED"),
+        flag(upper.contains("TEMP B-TREE"), "TEMP B-TREE"),
+        flag(first == "MATERIALIZE", "MATERIALIZED"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    node.detail = Some(detail.to_string());
+    node
+}
+
+fn plan_node(operation: String) -> PlanNode {
+    PlanNode {
+        operation,
+        detail: None,
+        table: None,
+        index: None,
+        startup_cost: None,
+        total_cost: None,
+        estimated_rows: None,
+        actual_rows: None,
+        actual_time_ms: None,
+        loops: None,
+        rows_removed: None,
+        blocks_hit: None,
+        blocks_read: None,
+        flags: Vec::new(),
+        children: Vec::new(),
+    }
+}
+
+// Work the flat steps don't point out: sorting without an index, indexes built
+// for one run, and subqueries that run again for every outer row
+fn tree_insights(plan: &PlanNode) -> Vec<String> {
+    let mut insights = Vec::new();
+    let detail = plan.detail.clone().unwrap_or_default();
+    for flag in &plan.flags {
+        match flag.as_str() {
+            "TEMP B-TREE" => insights.push(format!("Temporary b-tree: {}", detail)),
+            "AUTOMATIC INDEX" => insights.push(format!("Automatic index built for this query: {}", detail)),
+            "CORRELATED" => insights.push(format!("Correlated subquery runs once per outer row: {}", detail)),
+            _ => {}
+        }
+    }
+    for child in &plan.children {
+        insights.extend(tree_insights(child));
+    }
+    insights
+}
+
+// Row count of a table, and whether it was counted. COUNT(*) reads the whole
+// table, so unless asked for the figure comes from the statistics ANALYZE
+// leaves in sqlite_stat1 (or sqlite_stat4), and failing those the largest rowid
+pub(crate) fn table_rows(conn: &Connection, table: &str, exact: bool) -> (Option<u64>, bool) {
+    let quoted = sqlite_advisor::quote(table);
+    if exact {
+        let count = conn.query_row(&format!("SELECT COUNT(*) FROM {}", quoted), [], |r| r.get::<_, i64>(0)).ok();
+        return (count.map(|c| c as u64), count.is_some());
+    }
+    // Each stat starts with the number of rows in the table, or in a partial index of it
+    let stats: Vec<String> = conn
+        .prepare("SELECT stat FROM sqlite_stat1 WHERE tbl = ?1")
+        .and_then(|mut stmt| stmt.query_map([table], |r| r.get(0))?.collect())
+        .unwrap_or_default();
+    if let Some(rows) = stats.iter().filter_map(|stat| stat.split_whitespace().next()?.parse::<u64>().ok()).max() {
+        return (Some(rows), false);
+    }
+    // A sample's nlt and neq start with the rows before and equal to it on the
+    // index's first column; the last sample gives a floor
+    let sampled = conn.query_row(
+        "SELECT max(CAST(nlt AS INTEGER) + CAST(neq AS INTEGER)) FROM sqlite_stat4 WHERE tbl = ?1",
+        [table],
+        |r| r.get::<_, Option<i64>>(0),
+    );
+    if let Ok(Some(rows)) = sampled {
+        return (Some(rows as u64), false);
+    }
+    // Deleted rows leave gaps, so this is an upper bound; WITHOUT ROWID tables have no rowid
+    let max_rowid = conn.query_row(&format!("SELECT max(rowid) FROM {}", quoted), [], |r| r.get::<_, Option<i64>>(0));
+    (max_rowid.ok().map(|rowid| rowid.unwrap_or(0).max(0) as u64), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(schema: &str) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(schema).unwrap();
+        conn
+    }
+
+    #[test]
+    fn subquery_steps_nest_under_their_parent() {
+        let conn = database("CREATE TABLE parent(id INTEGER PRIMARY KEY, rank INT); CREATE TABLE child(id INTEGER PRIMARY KEY, parent_id INT);");
+        let (plan, steps) = explain(&conn, "SELECT id, (SELECT count(*) FROM child WHERE child.parent_id = parent.id) FROM parent ORDER BY rank").unwrap();
+        assert_eq!(plan.operation, "QUERY PLAN");
+        assert_eq!(steps.len(), 4);
+        let operations: Vec<&str> = plan.children.iter().map(|c| c.operation.as_str()).collect();
+        assert_eq!(operations, ["SCAN", "CORRELATED SCALAR SUBQUERY", "USE TEMP B-TREE"]);
+        assert_eq!(plan.children[0].table.as_deref(), Some("parent"));
+        let subquery = &plan.children[1];
+        assert_eq!(subquery.flags, ["CORRELATED"]);
+        assert_eq!(subquery.children.len(), 1);
+        assert_eq!(subquery.children[0].table.as_deref(), Some("child"));
+    }
+
+    #[test]
+    fn index_search_names_the_index() {
+        let conn = database("CREATE TABLE tag(id INTEGER PRIMARY KEY, label TEXT); CREATE INDEX tag_label ON tag(label);");
+        let (plan, _) = explain(&conn, "SELECT * FROM tag WHERE label = 'a'").unwrap();
+        let search = &plan.children[0];
+        assert_eq!(search.operation, "SEARCH");
+        assert_eq!(search.index.as_deref(), Some("tag_label"));
+    }
+
+    #[test]
+    fn aliased_tables_are_reported_by_name() {
+        let conn = database("CREATE TABLE item(id INTEGER PRIMARY KEY, price INT); INSERT INTO item(price) VALUES (1), (2), (3);");
+        let analysis = analyze(&conn, "SELECT * FROM item x WHERE x.price > 1", true).unwrap();
+        assert_eq!(analysis.tables.len(), 1);
+        assert_eq!(analysis.tables[0].table, "item");
+        assert_eq!(analysis.tables[0].total_rows, Some(3));
+        assert_eq!(analysis.rows_scanned_estimate, 3);
+    }
+
+    #[test]
+    fn quoted_table_names_are_counted() {
+        let conn = database("CREATE TABLE \"a\"\"b\"(id INTEGER PRIMARY KEY); CREATE TABLE ab(id INTEGER PRIMARY KEY); INSERT INTO \"a\"\"b\" VALUES (1), (2);");
+        assert_eq!(table_rows(&conn, "a\"b", true), (Some(2), true));
+        assert_eq!(table_rows(&conn, "a\"b", false), (Some(2), false));
+    }
+}
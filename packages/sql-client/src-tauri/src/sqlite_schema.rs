@@ -0,0 +1,492 @@
+// SQLite schema introspection for the sidebar and the ER view. Relations come
+// from PRAGMA table_list, which tells virtual tables and the shadow tables
+// their modules keep apart from plain ones; columns and keys from PRAGMA
+// table_xinfo, foreign_key_list, index_list and index_info. Collations,
+// AUTOINCREMENT and generated column expressions only appear in CREATE TABLE.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use rusqlite::{params, Connection};
+
+use crate::sql_split::{self, Dialect};
+use crate::{sqlite_advisor, DbSchemaSummary, ForeignKeyEdge, ForeignKeyViolations, TableColumn, TableInfo, TableKey, TriggerInfo};
+
+// Every user table, view and virtual table with their triggers, or only `table`
+// when given. Shadow tables only clutter the list, so they are left out unless asked for
+pub(crate) fn summary(conn: &Connection, table: Option<&str>, include_shadow: bool) -> Result<DbSchemaSummary, String> {
+    let mut table_stmt = conn
+        .prepare(
+            "SELECT t.name, t.type, m.sql, t.wr, t.strict FROM pragma_table_list t JOIN sqlite_master m ON m.name = t.name AND m.type IN ('table', 'view') \
+             WHERE t.schema = 'main' AND t.type IN ('table', 'view', 'virtual', 'shadow') \
+             AND (t.name = ?1 OR (?1 IS NULL AND t.name NOT LIKE 'sqlite_%' AND (?2 OR t.type <> 'shadow'))) ORDER BY t.name",
+        )
+        .map_err(|e| format!("Prepare tables error: {}", e))?;
+    let relations: Vec<(String, String, Option<String>, bool, bool)> = table_stmt
+        .query_map(params![table, include_shadow], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
+        .map_err(|e| format!("Tables query error: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Error reading table name: {}", e))?;
+
+    let mut tables: Vec<TableInfo> = Vec::new();
+    let mut foreign_keys: Vec<ForeignKeyEdge> = Vec::new();
+    for (table_name, table_type, sql, without_rowid, strict) in relations {
+        let kind = match table_type.as_str() {
+            "view" => "VIEW",
+            "virtual" => "VIRTUAL_TABLE",
+            "shadow" => "SHADOW_TABLE",
+            _ => "TABLE",
+        };
+        let mut info = table_info(conn, table_name, kind, sql.as_deref(), &mut foreign_keys)?;
+        let flag = |present: bool, name: &str| present.then(|| name.to_string());
+        info.flags = [flag(without_rowid, "WITHOUT ROWID"), flag(strict, "STRICT")].into_iter().flatten().collect();
+        // CREATE VIRTUAL TABLE name USING module(args)
+        if kind == "VIRTUAL_TABLE" {
+            let tokens = sql_split::tokens(sql.as_deref().unwrap_or(""), Dialect::Sqlite);
+            info.module = tokens
+                .iter()
+                .position(|t| t.eq_ignore_ascii_case("USING"))
+                .and_then(|i| tokens.get(i + 1))
+                .and_then(|t| sqlite_advisor::identifier(t));
+        }
+        tables.push(info);
+    }
+
+    let mut trigger_stmt = conn
+        .prepare("SELECT name, tbl_name, sql FROM sqlite_master WHERE type = 'trigger' AND (?1 IS NULL OR tbl_name = ?1) ORDER BY tbl_name, name")
+        .map_err(|e| format!("Prepare triggers error: {}", e))?;
+    let triggers = trigger_stmt
+        .query_map(params![table], |row| Ok(trigger_info(row.get(0)?, row.get(1)?, &row.get::<_, String>(2)?)))
+        .map_err(|e| format!("Triggers query error: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Error reading trigger: {}", e))?;
+    Ok(DbSchemaSummary { tables, foreign_keys, schemas: None, triggers: Some(triggers) })
+}
+
+// Timing, event, condition and body of a trigger, read from its CREATE TRIGGER:
+//   CREATE [TEMP] TRIGGER [IF NOT EXISTS] [schema.]name [BEFORE | AFTER | INSTEAD OF]
+//     DELETE | INSERT | UPDATE [OF column, ...] ON table [FOR EACH ROW] [WHEN expr] BEGIN ... END
+fn trigger_info(name: String, table: String, sql: &str) -> TriggerInfo {
+    let tokens = sql_split::tokens(sql, Dialect::Sqlite);
+    let upper: Vec<String> = tokens.iter().map(|t| t.to_ascii_uppercase()).collect();
+    let word = |i: usize| upper.get(i).map(String::as_str).unwrap_or("");
+
+    let mut i = upper.iter().position(|t| t == "TRIGGER").map_or(upper.len(), |i| i + 1);
+    if word(i) == "IF" {
+        i += 3;
+    }
+    i += if word(i + 1) == "." { 3 } else { 1 };
+    let timing = match word(i) {
+        "BEFORE" | "AFTER" => {
+            i += 1;
+            word(i - 1).to_string()
+        }
+        "INSTEAD" => {
+            i += 2;
+            "INSTEAD OF".to_string()
+        }
+        _ => "BEFORE".to_string(), // SQLite's default
+    };
+    let event = word(i).to_string();
+    let on = (i..upper.len()).find(|&j| word(j) == "ON").unwrap_or(upper.len());
+    let columns = if word(i + 1) == "OF" {
+        tokens[(i + 2).min(on)..on].iter().filter_map(|t| sqlite_advisor::identifier(t)).collect()
+    } else {
+        Vec::new()
+    };
+    let begin = (on..upper.len()).find(|&j| word(j) == "BEGIN").unwrap_or(upper.len());
+    let when = (on..begin).find(|&j| word(j) == "WHEN").map(|w| text_between(sql, &tokens, w + 1, begin).to_string());
+    let end = upper.iter().rposition(|t| t == "END").filter(|&e| e > begin).unwrap_or(upper.len());
+    let body = text_between(sql, &tokens, begin + 1, end).to_string();
+    TriggerInfo { name, table, timing, event, columns, when, body }
+}
+
+// Columns and keys of one table; its foreign keys are also added to `foreign_keys`
+fn table_info(conn: &Connection, table_name: String, kind: &str, sql: Option<&str>, foreign_keys: &mut Vec<ForeignKeyEdge>) -> Result<TableInfo, String> {
+    let mut info = TableInfo { name: table_name.clone(), columns: Vec::new(), keys: Vec::new(), schema: None, kind: kind.into(), engine: None, collation: None, auto_increment: None, module: None, flags: Vec::new() };
+    // columns, generated ones included; hidden columns of virtual tables are left out
+    let mut clauses = match kind {
+        "TABLE" | "SHADOW_TABLE" => column_clauses(sql.unwrap_or("")),
+        _ => HashMap::new(),
+    };
+    let mut col_stmt = match conn.prepare(&format!("PRAGMA table_xinfo('{}')", table_name.replace("'", "''"))) {
+        Ok(stmt) => stmt,
+        // A virtual table's columns come from its module, which may not be loaded here
+        Err(_) if kind == "VIRTUAL_TABLE" => return Ok(info),
+        Err(e) => return Err(format!("Prepare table_xinfo error: {}", e)),
+    };
+    let cols_iter = col_stmt
+        .query_map([], |row| {
+            let name: String = row.get(1)?;
+            let data_type: Option<String> = row.get::<_, Option<String>>(2)?;
+            let not_null: i64 = row.get(3)?;
+            let default_value: Option<String> = row.get(4)?;
+            let pk: i64 = row.get(5)?;
+            // 0 normal, 1 hidden, 2 virtual generated, 3 stored generated
+            let hidden: i64 = row.get(6)?;
+            let generated = match hidden {
+                2 => Some("VIRTUAL".to_string()),
+                3 => Some("STORED".to_string()),
+                _ => None,
+            };
+            let clause = clauses.remove(&name.to_lowercase()).unwrap_or_default();
+            Ok((hidden != 1).then_some(TableColumn {
+                name,
+                data_type,
+                not_null: not_null != 0,
+                pk: pk != 0,
+                pk_ordinal: (pk > 0).then_some(pk as u32),
+                default_value,
+                collation: clause.collation,
+                auto_increment: clause.auto_increment,
+                generation_expression: clause.generation_expression.filter(|_| generated.is_some()),
+                generated,
+            }))
+        })
+        .map_err(|e| format!("table_xinfo query error: {}", e))?;
+    let mut cols: Vec<TableColumn> = Vec::new();
+    let mut pk_cols: Vec<(u32, String)> = Vec::new();
+    for c in cols_iter {
+        let Some(col) = c.map_err(|e| format!("column row error: {}", e))? else { continue };
+        if let Some(ordinal) = col.pk_ordinal { pk_cols.push((ordinal, col.name.clone())); }
+        cols.push(col);
+    }
+    pk_cols.sort();
+    let pk_cols: Vec<String> = pk_cols.into_iter().map(|(_, name)| name).collect();
+    let mut keys: Vec<TableKey> = Vec::new();
+    if !pk_cols.is_empty() {
+        keys.push(TableKey { key_type: "PRIMARY_KEY".into(), columns: pk_cols.clone(), ..Default::default() });
+    }
+
+    // foreign keys per table, in declaration order; also accumulate global edges
+    for (_, edge) in table_foreign_keys(conn, &table_name, sql)? {
+        keys.push(TableKey::foreign_key(&edge, None));
+        foreign_keys.push(edge);
+    }
+
+    // indexes
+    let mut idx_stmt = conn
+        .prepare(&format!("PRAGMA index_list('{}')", table_name.replace("'", "''")))
+        .map_err(|e| format!("Prepare index_list error: {}", e))?;
+    let idx_iter = idx_stmt
+        .query_map([], |row| {
+            // seq, name, unique, origin, partial
+            let name: String = row.get(1)?;
+            let unique: i64 = row.get(2)?;
+            Ok((name, unique != 0))
+        })
+        .map_err(|e| format!("index_list query error: {}", e))?;
+    for idx in idx_iter {
+        let (idx_name, unique) = idx.map_err(|e| format!("index row error: {}", e))?;
+        if idx_name.starts_with("sqlite_autoindex") { continue; }
+        let mut info_stmt = conn
+            .prepare(&format!("PRAGMA index_info('{}')", idx_name.replace("'", "''")))
+            .map_err(|e| format!("Prepare index_info error: {}", e))?;
+        let info_iter = info_stmt
+            .query_map([], |row| {
+                // seqno, cid, name
+                let col_name: String = row.get(2)?;
+                Ok(col_name)
+            })
+            .map_err(|e| format!("index_info query error: {}", e))?;
+        let mut index_cols: Vec<String> = Vec::new();
+        for c in info_iter { index_cols.push(c.map_err(|e| format!("index_info row error: {}", e))?); }
+        keys.push(TableKey { key_type: "INDEX".into(), name: Some(idx_name), columns: index_cols, unique: Some(unique), ..Default::default() });
+    }
+
+    info.columns = cols;
+    info.keys = keys;
+    Ok(info)
+}
+
+// id, table, from, to (None for the parent's primary key), on_update, on_delete, match
+type ForeignKeyListRow = (i64, String, String, Option<String>, String, String, String);
+
+// Foreign keys of one table with their PRAGMA ids, in declaration order; `sql`
+// is its CREATE TABLE, for the deferral PRAGMA foreign_key_list leaves out
+fn table_foreign_keys(conn: &Connection, table_name: &str, sql: Option<&str>) -> Result<Vec<(i64, ForeignKeyEdge)>, String> {
+    let mut fk_stmt = conn
+        .prepare(&format!("PRAGMA foreign_key_list('{}')", table_name.replace("'", "''")))
+        .map_err(|e| format!("Prepare foreign_key_list error: {}", e))?;
+    // columns: id, seq, table, from, to, on_update, on_delete, match; one row per column pair
+    let rows: Vec<ForeignKeyListRow> = fk_stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?)))
+        .map_err(|e| format!("foreign_key_list query error: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("fk row error: {}", e))?;
+
+    let mut edges: Vec<(i64, ForeignKeyEdge)> = Vec::new();
+    for (id, to_table, from_col, to_col, on_update, on_delete, match_type) in rows {
+        match edges.last_mut() {
+            Some((last, edge)) if *last == id => {
+                edge.from_columns.push(from_col);
+                edge.to_columns.extend(to_col);
+            }
+            _ => edges.push((
+                id,
+                ForeignKeyEdge {
+                    from_table: table_name.to_string(),
+                    from_columns: vec![from_col],
+                    to_table,
+                    to_columns: to_col.into_iter().collect(),
+                    from_schema: None,
+                    to_schema: None,
+                    on_update: Some(on_update),
+                    on_delete: Some(on_delete),
+                    match_type: Some(match_type),
+                    deferrable: None,
+                    initially_deferred: None,
+                },
+            )),
+        }
+    }
+    // ids count back from the last key declared
+    edges.reverse();
+    let deferral = sql.map(deferral_clauses).unwrap_or_default();
+    let counted = deferral.len() == edges.len();
+    for (n, (_, edge)) in edges.iter_mut().enumerate() {
+        // REFERENCES without columns points at the parent's primary key
+        if edge.to_columns.is_empty() {
+            edge.to_columns = conn
+                .prepare("SELECT name FROM pragma_table_info(?1) WHERE pk > 0 ORDER BY pk")
+                .and_then(|mut stmt| stmt.query_map([&edge.to_table], |r| r.get(0))?.collect())
+                .unwrap_or_default();
+        }
+        if counted {
+            (edge.deferrable, edge.initially_deferred) = (Some(deferral[n].0), Some(deferral[n].1));
+        }
+    }
+    Ok(edges)
+}
+
+// Rows breaking each foreign key of `table`, or of every table, per PRAGMA foreign_key_check
+pub(crate) fn foreign_key_check(conn: &Connection, table: Option<&str>) -> Result<Vec<ForeignKeyViolations>, String> {
+    let pragma = match table {
+        Some(table) => format!("PRAGMA foreign_key_check('{}')", table.replace("'", "''")),
+        None => "PRAGMA foreign_key_check".to_string(),
+    };
+    let mut check_stmt = conn.prepare(&pragma).map_err(|e| format!("Prepare foreign_key_check error: {}", e))?;
+    // columns: table, rowid, parent, fkid; rowid is NULL for WITHOUT ROWID tables
+    let rows: Vec<(String, Option<i64>, i64)> = check_stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(3)?)))
+        .map_err(|e| format!("foreign_key_check query error: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("foreign_key_check row error: {}", e))?;
+
+    let mut edges: HashMap<String, Vec<(i64, ForeignKeyEdge)>> = HashMap::new();
+    let mut violations: Vec<(String, i64, ForeignKeyViolations)> = Vec::new();
+    for (table_name, rowid, fkid) in rows {
+        if let Some((_, _, found)) = violations.iter_mut().find(|(t, id, _)| *t == table_name && *id == fkid) {
+            found.row_count += 1;
+            found.rowids.extend(rowid);
+            continue;
+        }
+        if !edges.contains_key(&table_name) {
+            let sql: Option<String> = conn
+                .query_row("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1", params![table_name], |r| r.get(0))
+                .map_err(|e| format!("Table sql error: {}", e))?;
+            edges.insert(table_name.clone(), table_foreign_keys(conn, &table_name, sql.as_deref())?);
+        }
+        let Some((_, edge)) = edges[&table_name].iter().find(|(id, _)| *id == fkid) else { continue };
+        let found = ForeignKeyViolations { edge: edge.clone(), row_count: 1, rowids: rowid.into_iter().collect() };
+        violations.push((table_name, fkid, found));
+    }
+    Ok(violations.into_iter().map(|(_, _, found)| found).collect())
+}
+
+// Text of `sql` from token `from` up to token `to` (or the end), trimmed.
+// Tokens are slices of `sql`, so their offsets recover the text between them
+fn text_between<'a>(sql: &'a str, tokens: &[&str], from: usize, to: usize) -> &'a str {
+    let offset = |i: usize| tokens.get(i).map_or(sql.len(), |t| t.as_ptr() as usize - sql.as_ptr() as usize);
+    sql[offset(from).min(offset(to))..offset(to)].trim()
+}
+
+// Clauses of a column definition that no PRAGMA reports
+#[derive(Default)]
+struct ColumnClauses {
+    collation: Option<String>,
+    auto_increment: bool,
+    generation_expression: Option<String>,
+}
+
+// Column definitions of a CREATE TABLE, by lowercased column name:
+//   CREATE TABLE name (column [type] [COLLATE name] [PRIMARY KEY AUTOINCREMENT]
+//     [[GENERATED ALWAYS] AS (expr) [STORED | VIRTUAL]] ..., [table constraint, ...]) [options]
+fn column_clauses(sql: &str) -> HashMap<String, ColumnClauses> {
+    let tokens = sql_split::tokens(sql, Dialect::Sqlite);
+    let mut clauses = HashMap::new();
+    for span in definitions(&tokens) {
+        let first = tokens.get(span.start).copied().unwrap_or("");
+        if ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"].iter().any(|k| first.eq_ignore_ascii_case(k)) {
+            continue;
+        }
+        let Some(name) = sqlite_advisor::identifier(first) else { continue };
+        let mut column = ColumnClauses::default();
+        let mut depth = 0;
+        for i in span.clone() {
+            match tokens[i] {
+                "(" => depth += 1,
+                ")" => depth -= 1,
+                _ if depth > 0 => {}
+                word if word.eq_ignore_ascii_case("COLLATE") => column.collation = tokens.get(i + 1).and_then(|t| sqlite_advisor::identifier(t)),
+                word if word.eq_ignore_ascii_case("AUTOINCREMENT") => column.auto_increment = true,
+                word if word.eq_ignore_ascii_case("AS") && tokens.get(i + 1) == Some(&"(") => {
+                    let mut inner = 0;
+                    let close = (i + 1..span.end).find(|&j| {
+                        match tokens[j] {
+                            "(" => inner += 1,
+                            ")" => inner -= 1,
+                            _ => {}
+                        }
+                        inner == 0
+                    });
+                    column.generation_expression = close.map(|close| text_between(sql, &tokens, i + 2, close).to_string());
+                }
+                _ => {}
+            }
+        }
+        clauses.insert(name.to_lowercase(), column);
+    }
+    clauses
+}
+
+// Token spans of the comma-separated definitions inside the outer parentheses
+// of a CREATE TABLE; none for CREATE TABLE ... AS SELECT
+fn definitions(tokens: &[&str]) -> Vec<Range<usize>> {
+    let mut definitions = Vec::new();
+    let Some(open) = tokens.iter().position(|t| *t == "(" || t.eq_ignore_ascii_case("AS")).filter(|&i| tokens[i] == "(") else {
+        return definitions;
+    };
+    let (mut depth, mut start) = (0, open + 1);
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match *token {
+            "(" => depth += 1,
+            ")" => {
+                depth -= 1;
+                if depth == 0 {
+                    definitions.push(start..i);
+                    break;
+                }
+            }
+            "," if depth == 1 => {
+                definitions.push(start..i);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    definitions
+}
+
+// (deferrable, initially deferred) of each REFERENCES clause in a CREATE TABLE,
+// in declaration order; PRAGMA foreign_key_list doesn't report it
+//   REFERENCES parent [(columns)] [ON ... | MATCH ...] [[NOT] DEFERRABLE [INITIALLY DEFERRED | IMMEDIATE]]
+fn deferral_clauses(sql: &str) -> Vec<(bool, bool)> {
+    let tokens = sql_split::tokens(sql, Dialect::Sqlite);
+    let word = |i: usize| tokens.get(i).copied().unwrap_or("");
+    let mut clauses = Vec::new();
+    for span in definitions(&tokens) {
+        let mut depth = 0;
+        let mut top = Vec::new(); // indexes of the definition's tokens outside parentheses
+        for i in span {
+            match tokens[i] {
+                "(" => depth += 1,
+                ")" => depth -= 1,
+                _ if depth == 0 => top.push(i),
+                _ => {}
+            }
+        }
+        for (n, &i) in top.iter().enumerate() {
+            if !word(i).eq_ignore_ascii_case("REFERENCES") {
+                continue;
+            }
+            let deferrable = top[n + 1..]
+                .iter()
+                .take_while(|&&j| !word(j).eq_ignore_ascii_case("REFERENCES"))
+                .find(|&&j| word(j).eq_ignore_ascii_case("DEFERRABLE"))
+                .filter(|&&j| !word(j - 1).eq_ignore_ascii_case("NOT"));
+            let initially_deferred = deferrable.is_some_and(|&j| word(j + 1).eq_ignore_ascii_case("INITIALLY") && word(j + 2).eq_ignore_ascii_case("DEFERRED"));
+            clauses.push((deferrable.is_some(), initially_deferred));
+        }
+    }
+    clauses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(schema: &str) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(schema).unwrap();
+        conn
+    }
+
+    #[test]
+    fn tables_views_and_triggers() {
+        let conn = database(
+            "CREATE TABLE account(id INTEGER PRIMARY KEY, balance REAL);
+             CREATE VIEW overdrawn AS SELECT * FROM account WHERE balance < 0;
+             CREATE TRIGGER account_limit AFTER UPDATE OF balance ON account WHEN new.balance < -100 BEGIN SELECT RAISE(ABORT, 'limit'); END;",
+        );
+        let summary = summary(&conn, None, false).unwrap();
+        let names: Vec<(&str, &str)> = summary.tables.iter().map(|t| (t.name.as_str(), t.kind.as_str())).collect();
+        assert_eq!(names, [("account", "TABLE"), ("overdrawn", "VIEW")]);
+        let triggers = summary.triggers.unwrap();
+        assert_eq!(triggers.len(), 1);
+        assert_eq!((triggers[0].timing.as_str(), triggers[0].event.as_str()), ("AFTER", "UPDATE"));
+        assert_eq!(triggers[0].columns, ["balance"]);
+        assert_eq!(triggers[0].when.as_deref(), Some("new.balance < -100"));
+    }
+
+    #[test]
+    fn column_details() {
+        let conn = database("CREATE TABLE person(id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT COLLATE NOCASE NOT NULL, upper_name TEXT AS (upper(name)));");
+        let summary = summary(&conn, Some("person"), false).unwrap();
+        let columns = &summary.tables[0].columns;
+        assert_eq!(columns.len(), 3);
+        assert!(columns[0].pk && columns[0].auto_increment);
+        assert_eq!(columns[0].pk_ordinal, Some(1));
+        assert_eq!(columns[1].collation.as_deref(), Some("NOCASE"));
+        assert!(columns[1].not_null);
+        assert_eq!(columns[2].generated.as_deref(), Some("VIRTUAL"));
+        assert_eq!(columns[2].generation_expression.as_deref(), Some("upper(name)"));
+    }
+
+    const LIBRARY: &str = "CREATE TABLE author(id INTEGER PRIMARY KEY, name TEXT);
+        CREATE TABLE book(id INTEGER PRIMARY KEY, author_id INT REFERENCES author(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED, year INT);
+        CREATE UNIQUE INDEX book_author_year ON book(author_id, year);";
+
+    #[test]
+    fn foreign_keys_and_indexes() {
+        let summary = summary(&database(LIBRARY), Some("book"), false).unwrap();
+        assert_eq!(summary.foreign_keys.len(), 1);
+        let edge = &summary.foreign_keys[0];
+        assert_eq!((edge.from_table.as_str(), edge.to_table.as_str()), ("book", "author"));
+        assert_eq!(edge.from_columns, ["author_id"]);
+        assert_eq!(edge.to_columns, ["id"]);
+        assert_eq!(edge.on_delete.as_deref(), Some("CASCADE"));
+        assert_eq!((edge.deferrable, edge.initially_deferred), (Some(true), Some(true)));
+        let index = summary.tables[0].keys.iter().find(|k| k.key_type == "INDEX").unwrap();
+        assert_eq!(index.name.as_deref(), Some("book_author_year"));
+        assert_eq!(index.columns, ["author_id", "year"]);
+        assert_eq!(index.unique, Some(true));
+    }
+
+    #[test]
+    fn violations_are_grouped_by_foreign_key() {
+        let conn = database(LIBRARY);
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             INSERT INTO author(name) VALUES ('a');
+             INSERT INTO book(id, author_id, year) VALUES (1, 1, 2000), (2, 7, 2000), (3, 8, 2001);",
+        )
+        .unwrap();
+        let violations = foreign_key_check(&conn, None).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].edge.to_table, "author");
+        assert_eq!(violations[0].row_count, 2);
+        assert_eq!(violations[0].rowids, [2, 3]);
+        assert!(foreign_key_check(&conn, Some("author")).unwrap().is_empty());
+    }
+}
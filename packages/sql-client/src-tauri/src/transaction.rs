@@ -0,0 +1,222 @@
+// Transaction bookkeeping for a session.
+//
+// Network drivers don't report whether a transaction is open, so the status is
+// tracked from the statements a session runs, including BEGIN/COMMIT/SAVEPOINT
+// typed into a script. SQLite sessions overwrite `active` and `pending_changes`
+// with what the connection itself reports.
+
+use serde::Serialize;
+
+use crate::sql_split::Dialect;
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TransactionStatus {
+    pub(crate) active: bool,
+    pub(crate) pending_changes: bool, // Something a rollback would discard
+    pub(crate) savepoints: Vec<String>, // Innermost last
+}
+
+impl TransactionStatus {
+    // Updates the status after `sql` ran successfully; `writes` is whether the
+    // statement may have changed data
+    pub(crate) fn observe(&mut self, sql: &str, writes: bool, dialect: Dialect) {
+        let words: Vec<&str> = sql.split_whitespace().collect();
+        let Some(first) = words.first() else { return };
+        let is = |word: &str, keyword: &str| word.eq_ignore_ascii_case(keyword);
+        // Savepoint name ends SAVEPOINT/RELEASE/ROLLBACK TO
+        let name = || trailing_name(sql);
+
+        if is(first, "BEGIN") || (is(first, "START") && words.get(1).is_some_and(|w| is(w, "TRANSACTION"))) {
+            *self = TransactionStatus { active: true, ..Default::default() };
+        } else if is(first, "COMMIT") || is(first, "END") || is(first, "ABORT") {
+            *self = TransactionStatus::default();
+        } else if is(first, "ROLLBACK") {
+            if words.iter().any(|w| is(w, "TO")) {
+                if let Some(pos) = self.savepoint(&name()) {
+                    self.savepoints.truncate(pos + 1);
+                }
+            } else {
+                *self = TransactionStatus::default();
+            }
+        } else if is(first, "SAVEPOINT") {
+            // SQLite starts a transaction for a SAVEPOINT outside one
+            self.active = true;
+            self.savepoints.push(name());
+        } else if is(first, "RELEASE") {
+            if let Some(pos) = self.savepoint(&name()) {
+                self.savepoints.truncate(pos);
+            }
+        } else if dialect == Dialect::Mysql && (is(first, "CREATE") || is(first, "DROP")) && words.get(1).is_some_and(|w| is(w, "TEMPORARY")) {
+            // MySQL temporary tables neither commit nor go away on rollback
+        } else if dialect == Dialect::Mysql && ["CREATE", "ALTER", "DROP", "RENAME", "TRUNCATE"].iter().any(|k| is(first, k)) {
+            // MySQL commits implicitly before and after DDL
+            *self = TransactionStatus::default();
+        } else if self.active && writes && !is(first, "SET") {
+            self.pending_changes = true;
+        }
+    }
+
+    // Innermost savepoint named `name`. Names are matched case-insensitively,
+    // as SQLite and MySQL do and as Postgres does for unquoted names
+    fn savepoint(&self, name: &str) -> Option<usize> {
+        self.savepoints.iter().rposition(|s| s.eq_ignore_ascii_case(name))
+    }
+}
+
+// Transaction control requested through the transaction commands
+pub(crate) enum TransactionOp {
+    Begin,
+    Commit,
+    Rollback,
+    Savepoint(String),
+    Release(String),
+    RollbackTo(String),
+}
+
+impl TransactionOp {
+    // Statement for this operation, refused up front when it doesn't fit the current status
+    pub(crate) fn sql(&self, status: &TransactionStatus, dialect: Dialect) -> Result<String, String> {
+        match self {
+            TransactionOp::Begin if status.active => return Err("A transaction is already open".into()),
+            TransactionOp::Begin => {}
+            _ if !status.active => return Err("No open transaction".into()),
+            TransactionOp::Savepoint(name) | TransactionOp::Release(name) | TransactionOp::RollbackTo(name) if name.trim().is_empty() => {
+                return Err("Savepoint name is required".into())
+            }
+            _ => {}
+        }
+        Ok(match self {
+            TransactionOp::Begin if dialect == Dialect::Mysql => "START TRANSACTION".to_string(),
+            TransactionOp::Begin => "BEGIN".to_string(),
+            TransactionOp::Commit => "COMMIT".to_string(),
+            TransactionOp::Rollback => "ROLLBACK".to_string(),
+            TransactionOp::Savepoint(name) => format!("SAVEPOINT {}", quote_ident(name, dialect)),
+            TransactionOp::Release(name) => format!("RELEASE SAVEPOINT {}", quote_ident(name, dialect)),
+            TransactionOp::RollbackTo(name) => format!("ROLLBACK TO SAVEPOINT {}", quote_ident(name, dialect)),
+        })
+    }
+
+    // Status after the statement from `sql` succeeded
+    pub(crate) fn apply(&self, status: &mut TransactionStatus) {
+        match self {
+            TransactionOp::Begin => *status = TransactionStatus { active: true, ..Default::default() },
+            TransactionOp::Commit | TransactionOp::Rollback => *status = TransactionStatus::default(),
+            TransactionOp::Savepoint(name) => status.savepoints.push(name.clone()),
+            TransactionOp::Release(name) => {
+                if let Some(pos) = status.savepoint(name) {
+                    status.savepoints.truncate(pos);
+                }
+            }
+            TransactionOp::RollbackTo(name) => {
+                if let Some(pos) = status.savepoint(name) {
+                    status.savepoints.truncate(pos + 1);
+                }
+            }
+        }
+    }
+}
+
+// Quoted identifier for SAVEPOINT and friends
+fn quote_ident(name: &str, dialect: Dialect) -> String {
+    match dialect {
+        Dialect::Mysql => format!("`{}`", name.replace('`', "``")),
+        Dialect::Sqlite | Dialect::Postgres => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+// Last identifier of a statement; a quoted one may contain spaces
+fn trailing_name(sql: &str) -> String {
+    let sql = sql.trim_end().trim_end_matches(';').trim_end();
+    let start = match sql.chars().last() {
+        Some(q @ ('"' | '`')) => sql[..sql.len() - 1].rfind(q),
+        _ => sql.rfind(char::is_whitespace).map(|i| i + 1),
+    };
+    unquote(&sql[start.unwrap_or(0)..])
+}
+
+fn unquote(word: &str) -> String {
+    match word.chars().next() {
+        Some(q @ ('"' | '`')) if word.len() >= 2 && word.ends_with(q) => {
+            let doubled: String = [q, q].iter().collect();
+            word[1..word.len() - 1].replace(&doubled, &q.to_string())
+        }
+        _ => word.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observed(statements: &[(&str, bool)], dialect: Dialect) -> TransactionStatus {
+        let mut status = TransactionStatus::default();
+        for (sql, writes) in statements {
+            status.observe(sql, *writes, dialect);
+        }
+        status
+    }
+
+    #[test]
+    fn begin_write_commit() {
+        let status = observed(&[("BEGIN", false), ("UPDATE t SET a = 1", true)], Dialect::Postgres);
+        assert!(status.active && status.pending_changes);
+        let status = observed(&[("begin", false), ("SELECT 1", false)], Dialect::Postgres);
+        assert!(status.active && !status.pending_changes);
+        let status = observed(&[("START TRANSACTION", false), ("INSERT INTO t VALUES (1)", true), ("COMMIT", false)], Dialect::Mysql);
+        assert!(!status.active && !status.pending_changes);
+        // Writes outside a transaction are committed as they run
+        assert!(!observed(&[("DELETE FROM t", true)], Dialect::Sqlite).pending_changes);
+    }
+
+    #[test]
+    fn savepoints_nest_and_unwind() {
+        let status = observed(&[("BEGIN", false), ("SAVEPOINT a", false), ("SAVEPOINT \"b c\"", false), ("SAVEPOINT d", false)], Dialect::Postgres);
+        assert_eq!(status.savepoints, ["a", "b c", "d"]);
+        let mut rolled_back = status.clone();
+        rolled_back.observe("ROLLBACK TO SAVEPOINT \"b c\"", false, Dialect::Postgres);
+        assert_eq!(rolled_back.savepoints, ["a", "b c"]);
+        let mut released = status.clone();
+        released.observe("RELEASE b", false, Dialect::Postgres);
+        assert_eq!(released.savepoints, ["a", "b c", "d"]);
+        released.observe("release savepoint `b c`;", false, Dialect::Mysql);
+        assert_eq!(released.savepoints, ["a"]);
+        released.observe("ROLLBACK", false, Dialect::Postgres);
+        assert!(!released.active && released.savepoints.is_empty());
+    }
+
+    #[test]
+    fn sqlite_savepoint_opens_a_transaction() {
+        let status = observed(&[("SAVEPOINT s", false)], Dialect::Sqlite);
+        assert!(status.active);
+        assert_eq!(status.savepoints, ["s"]);
+    }
+
+    #[test]
+    fn mysql_ddl_commits() {
+        let status = observed(&[("START TRANSACTION", false), ("INSERT INTO t VALUES (1)", true), ("CREATE TABLE u (a int)", true)], Dialect::Mysql);
+        assert!(!status.active && !status.pending_changes);
+        let status = observed(&[("BEGIN", false), ("CREATE TABLE u (a int)", true)], Dialect::Postgres);
+        assert!(status.active && status.pending_changes);
+        let status = observed(&[("START TRANSACTION", false), ("CREATE TEMPORARY TABLE t (a int)", true), ("DROP TEMPORARY TABLE t", true)], Dialect::Mysql);
+        assert!(status.active && !status.pending_changes);
+    }
+
+    #[test]
+    fn savepoint_names_match_without_case() {
+        let status = observed(&[("BEGIN", false), ("SAVEPOINT sp1", false), ("SAVEPOINT x", false), ("SAVEPOINT sp1", false), ("SAVEPOINT y", false)], Dialect::Postgres);
+        // Both paths unwind to the innermost savepoint of that name
+        let mut observed = status.clone();
+        observed.observe("ROLLBACK TO SP1", false, Dialect::Postgres);
+        let mut applied = status.clone();
+        TransactionOp::RollbackTo("SP1".into()).apply(&mut applied);
+        assert_eq!(observed.savepoints, ["sp1", "x", "sp1"]);
+        assert_eq!(applied.savepoints, observed.savepoints);
+        let mut observed = status.clone();
+        observed.observe("RELEASE SAVEPOINT Sp1", false, Dialect::Postgres);
+        let mut applied = status;
+        TransactionOp::Release("Sp1".into()).apply(&mut applied);
+        assert_eq!(observed.savepoints, ["sp1", "x"]);
+        assert_eq!(applied.savepoints, observed.savepoints);
+    }
+}
@@ -1,6 +1,6 @@
 import { useState } from "react";
 import { useAppStore } from "../store";
-import type { Connection } from "../types";
+import type { Connection, SqliteAccess } from "../types";
 import { Button } from "./ui/button";
 import { Input } from "./ui/input";
 import { Label } from "./ui/label";
@@ -21,7 +21,7 @@ export default function ConnectionsSettings() {
     if (!draft) return;
     addOrUpdate(draft);
     if (draft.driver === "sqlite" && draft.filePath) {
-      try { await invoke("sqlite_open", { connectionId: draft.id, filePath: draft.filePath }); } catch { /* ignore */ }
+      try { await invoke("sqlite_open", { connectionId: draft.id, filePath: draft.filePath, access: draft.sqliteAccess }); } catch { /* ignore */ }
     }
     setDraft(null);
   };
@@ -160,6 +160,16 @@ export default function ConnectionsSettings() {
                 >Browse</Button>
               </div>
             )}
+            {draft.driver === "sqlite" && (
+              <Label>
+                Access
+                <Select value={draft.sqliteAccess ?? "readOnly"} onChange={(e) => setDraft({ ...draft, sqliteAccess: e.target.value as SqliteAccess })}>
+                  <option value="readOnly">Read-only</option>
+                  <option value="readWrite">Read-write</option>
+                  <option value="readWriteCreate">Read-write, create if missing</option>
+                </Select>
+              </Label>
+            )}
           </div>
           <div className="flex items-center gap-2">
             <Button onClick={save}>Save</Button>
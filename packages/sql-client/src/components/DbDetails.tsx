@@ -1,6 +1,5 @@
 import { useEffect, useMemo, useState } from "react";
-import { invoke } from "@tauri-apps/api/core";
-import { useAppStore } from "../store";
+import { loadSchemaSummary, useAppStore } from "../store";
 import type { TABLE_TYPE } from "shared";
 import { Button } from "./ui/button";
 import { useDbIndexer } from "../hooks/useDbIndexer";
@@ -21,10 +20,7 @@ export default function DbDetails() {
       setSchema(null);
       try {
         if (!active) throw new Error("Select an active connection");
-        if (active.driver !== "sqlite") throw new Error("Only SQLite supported in this build");
-        if (!active.filePath) throw new Error("Select a SQLite file");
-        await invoke("sqlite_open", { connectionId: active.id, filePath: active.filePath });
-        const summary = await invoke<TABLE_TYPE.DbSchemaSummary>("sqlite_schema_summary", { connectionId: active.id });
+        const summary = await loadSchemaSummary(active);
         if (mounted) setSchema(summary);
       } catch (e) {
         if (mounted) setError((e as Error).message);
@@ -34,7 +30,7 @@ export default function DbDetails() {
     };
     load();
     return () => { mounted = false; };
-  }, [active?.id, active?.driver, active?.filePath]);
+  }, [active?.id, active?.driver, active?.filePath, active?.host, active?.database]);
 
   const { isIndexing, indexDb } = useDbIndexer();
 
@@ -1,8 +1,9 @@
 import { useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import { useAppStore } from "../store";
 import { ColumnDef, flexRender, getCoreRowModel, getPaginationRowModel, useReactTable } from "@tanstack/react-table";
 import Paginator from "./Paginator";
-import type { TableRow } from "../types";
+import type { IndexSuggestion, TableRow } from "../types";
 import { Table, Thead, Tbody, Tr, Th, Td } from "./ui/table";
 import { Tabs, TabsContent, TabsList, TabsTrigger } from "./ui/tabs";
  
@@ -12,17 +13,26 @@ export default function ResultTable() {
   const lastRun = useAppStore((s) => s.history.find((h) => h.id === s.lastRunId));
   const page = useAppStore((s) => s.page);
   const pageSize = useAppStore((s) => s.pageSize);
+  const runQuery = useAppStore((s) => s.runQuery);
+  const isSqlite = useAppStore((s) => s.connections.find((c) => c.id === lastRun?.connectionId)?.driver === "sqlite");
 
   // Query runs only on explicit Run trigger; pagination changes do not auto-run
 
-  const columns: ColumnDef<TableRow>[] = (result?.columns ?? []).map((col) => ({
+  // A script returns one set per statement; each gets its own tab, opening on the last
+  const resultSets = result?.resultSets && result.resultSets.length > 1 ? result.resultSets : null;
+  const [setIndex, setSetIndex] = useState(0);
+  useEffect(() => setSetIndex(Math.max(0, (result?.resultSets?.length ?? 1) - 1)), [result]);
+  const shownSet = resultSets?.[setIndex] ?? null;
+  const shown = shownSet ?? result;
+
+  const columns: ColumnDef<TableRow>[] = (shown?.columns ?? []).map((col) => ({
     header: col,
     accessorKey: col,
     cell: (info) => String(info.getValue() ?? ""),
   }));
 
   const table = useReactTable({
-    data: result?.rows ?? [],
+    data: shown?.rows ?? [],
     columns,
     getCoreRowModel: getCoreRowModel(),
     getPaginationRowModel: getPaginationRowModel(),
@@ -46,6 +56,25 @@ export default function ResultTable() {
     }
   }, [lastRun?.id, lastRun?.status, tabValue]);
 
+  // Suggestions re-checked against a copy of the schema replace the run's unchecked ones
+  const [checkedSuggestions, setCheckedSuggestions] = useState<IndexSuggestion[] | null>(null);
+  useEffect(() => setCheckedSuggestions(null), [result]);
+  const indexSuggestions = checkedSuggestions ?? result?.indexSuggestions ?? [];
+  // Table sizes are estimates until ANALYZE has recorded statistics; refresh them and run again
+  const analyzeTables = () => {
+    if (!lastRun) return;
+    const tables = (result?.planTables ?? []).map((t) => t.table);
+    invoke("sqlite_analyze", { connectionId: lastRun.connectionId, tables })
+      .then(() => runQuery())
+      .catch(() => { /* read-only files can't store statistics */ });
+  };
+  const checkSuggestions = () => {
+    if (!lastRun) return;
+    invoke<IndexSuggestion[]>("suggest_sqlite_indexes", { connectionId: lastRun.connectionId, sql: lastRun.sql })
+      .then(setCheckedSuggestions)
+      .catch(() => { /* keep the unchecked suggestions */ });
+  };
+
   return (
     <div className="flex-1 flex flex-col overflow-hidden">
       <Tabs value={tabValue} onValueChange={(v) => {
@@ -75,33 +104,52 @@ export default function ResultTable() {
             <div className="flex-1 flex items-center justify-center text-gray-500">No results</div>
           ) : (
             <>
-              <div className="flex-1 overflow-auto">
-                <Table className="table-auto border-collapse">
-                  <Thead>
-                    {table.getHeaderGroups().map((hg) => (
-                      <Tr key={hg.id}>
-                        {hg.headers.map((header) => (
-                          <Th key={header.id} className="sticky top-0">
-                            {header.isPlaceholder ? null : flexRender(header.column.columnDef.header, header.getContext())}
-                          </Th>
-                        ))}
-                      </Tr>
+              {resultSets && (
+                <Tabs value={String(setIndex)} onValueChange={(v) => setSetIndex(Number(v))} className="flex-none">
+                  <TabsList className="overflow-x-auto">
+                    {resultSets.map((set, idx) => (
+                      <TabsTrigger key={idx} value={String(idx)}>
+                        {set.columns.length > 0 ? `Result ${idx + 1} (${set.rowCount})` : `Statement ${idx + 1}`}
+                      </TabsTrigger>
                     ))}
-                  </Thead>
-                  <Tbody>
-                    {table.getRowModel().rows.map((row, rowIndex) => (
-                      <Tr key={row.id} className={rowIndex % 2 === 0 ? "bg-gray-950" : "bg-gray-900"}>
-                        {row.getVisibleCells().map((cell) => (
-                          <Td key={cell.id}>
-                            {flexRender(cell.column.columnDef.cell, cell.getContext())}
-                          </Td>
-                        ))}
-                      </Tr>
-                    ))}
-                  </Tbody>
-                </Table>
-              </div>
-              <Paginator />
+                  </TabsList>
+                </Tabs>
+              )}
+              {shownSet && shownSet.columns.length === 0 ? (
+                <div className="flex-1 p-3 text-sm font-mono text-gray-400">
+                  {shownSet.affectedRows != null ? `${shownSet.affectedRows} rows affected` : "Statement executed"}
+                  {` in ${shownSet.elapsedMs} ms`}
+                </div>
+              ) : (
+                <div className="flex-1 overflow-auto">
+                  <Table className="table-auto border-collapse">
+                    <Thead>
+                      {table.getHeaderGroups().map((hg) => (
+                        <Tr key={hg.id}>
+                          {hg.headers.map((header) => (
+                            <Th key={header.id} className="sticky top-0">
+                              {header.isPlaceholder ? null : flexRender(header.column.columnDef.header, header.getContext())}
+                            </Th>
+                          ))}
+                        </Tr>
+                      ))}
+                    </Thead>
+                    <Tbody>
+                      {table.getRowModel().rows.map((row, rowIndex) => (
+                        <Tr key={row.id} className={rowIndex % 2 === 0 ? "bg-gray-950" : "bg-gray-900"}>
+                          {row.getVisibleCells().map((cell) => (
+                            <Td key={cell.id}>
+                              {flexRender(cell.column.columnDef.cell, cell.getContext())}
+                            </Td>
+                          ))}
+                        </Tr>
+                      ))}
+                    </Tbody>
+                  </Table>
+                </div>
+              )}
+              {/* Scripts come back whole; only a single result pages */}
+              {!resultSets && <Paginator />}
             </>
           )}
         </TabsContent>
@@ -121,16 +169,22 @@ export default function ResultTable() {
               </div>
               <div>Page: {page + 1} | Rows/page: {pageSize}</div>
               {result?.rowsScannedEstimate !== undefined && (
-                <div>Estimated rows scanned: {result.rowsScannedEstimate}</div>
+                <div>Estimated rows scanned: {result.rowsScannedExact === false ? "~" : ""}{result.rowsScannedEstimate}</div>
               )}
               {result?.planTables && result.planTables.length > 0 && (
                 <div className="mt-1">
-                  <div className="text-emerald-300/80">Table access:</div>
+                  <div className="text-emerald-300/80">
+                    Table access:
+                    {isSqlite && result.planTables.some((t) => !t.totalRowsExact) && (
+                      <button className="ml-2 underline text-emerald-300" onClick={analyzeTables}>update statistics</button>
+                    )}
+                  </div>
                   <ul className="list-disc ml-5 space-y-0.5">
                     {result.planTables.map((t) => (
                       <li key={`${t.table}-${t.access}`}>
                         {t.table}: {t.access}
-                        {t.totalRows !== undefined && t.totalRows !== null ? ` (${t.totalRows} rows)` : ""}
+                        {t.accessType ? ` [${t.accessType}${t.key ? ` using ${t.key}` : ""}]` : ""}
+                        {t.totalRows !== undefined && t.totalRows !== null ? ` (${t.totalRowsExact ? "" : "~"}${t.totalRows} rows)` : ""}
                       </li>
                     ))}
                   </ul>
@@ -146,6 +200,25 @@ export default function ResultTable() {
                   </ul>
                 </div>
               )}
+              {indexSuggestions.length > 0 && (
+                <div className="mt-1">
+                  <div className="text-emerald-300/80">
+                    Suggested indexes:
+                    {!checkedSuggestions && (
+                      <button className="ml-2 underline text-emerald-300" onClick={checkSuggestions}>check on schema copy</button>
+                    )}
+                  </div>
+                  <ul className="list-disc ml-5 space-y-0.5">
+                    {indexSuggestions.map((s) => (
+                      <li key={s.statement}>
+                        <span className="select-all">{s.statement}</span>
+                        <span className="text-emerald-300/70"> ({s.reason})</span>
+                        {s.validated === true ? " [used by the plan]" : s.validated === false ? " [not used by the plan]" : ""}
+                      </li>
+                    ))}
+                  </ul>
+                </div>
+              )}
               {result?.planSteps && result.planSteps.length > 0 && (
                 <details className="mt-1">
                   <summary className="cursor-pointer text-emerald-300/80">Explain plan details</summary>
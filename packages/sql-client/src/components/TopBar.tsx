@@ -87,8 +87,9 @@ function IconClose(props: React.SVGProps<SVGSVGElement>) {
 // Removed old IconFluxQuery in favor of branded image in public/logo.png
 
 export default function TopBar({ toggleLeft, toggleRight }: TopBarProps) {
-  const { runQuery, cancelQuery } = useAppStore();
+  const { runQuery, cancelQuery, endTransaction } = useAppStore();
   const runningJobId = useAppStore((s) => s.runningJobId);
+  const transaction = useAppStore((s) => s.transaction);
   const wsStatus = useAppStore((s) => s.wsStatus);
   const initWebSocket = useAppStore((s) => s.initWebSocket);
 
@@ -201,6 +202,35 @@ export default function TopBar({ toggleLeft, toggleRight }: TopBarProps) {
             Disconnected
           </Badge>
         )}
+        {transaction?.active && (
+          <div className="flex items-center gap-1">
+            <Badge
+              className="font-mono"
+              title={transaction.savepoints.length ? `Savepoints: ${transaction.savepoints.join(" > ")}` : undefined}
+            >
+              {transaction.pendingChanges ? "Transaction · uncommitted changes" : "Transaction open"}
+              {transaction.savepoints.length > 0 && ` · ${transaction.savepoints.length} savepoint${transaction.savepoints.length > 1 ? "s" : ""}`}
+            </Badge>
+            <Button
+              size="sm"
+              variant="secondary"
+              className="font-mono"
+              disabled={!!runningJobId}
+              onClick={() => { void endTransaction("commit").catch((err) => console.error("Commit failed", err)); }}
+            >
+              Commit
+            </Button>
+            <Button
+              size="sm"
+              variant="destructive"
+              className="font-mono"
+              disabled={!!runningJobId}
+              onClick={() => { void endTransaction("rollback").catch((err) => console.error("Rollback failed", err)); }}
+            >
+              Rollback
+            </Button>
+          </div>
+        )}
         {runningJobId ? (
           <Button
             onClick={cancelQuery}
@@ -2,9 +2,8 @@ import { useEffect, useMemo, useState } from "react";
 import ReactFlow, { Background, Controls, MiniMap, type Edge, type Node, useEdgesState, useNodesState } from "reactflow";
 import "reactflow/dist/style.css";
 import dagre from "dagre";
-import { useAppStore } from "../store";
+import { loadSchemaSummary, useAppStore } from "../store";
 import type { TABLE_TYPE } from "shared";
-import { invoke } from "@tauri-apps/api/core";
 
 const nodeWidth = 280;
 const nodeHeight = 120;
@@ -42,10 +41,7 @@ export default function VisualizerStub() {
       setError(null);
       try {
         if (!active) throw new Error("Select an active connection");
-        if (active.driver !== "sqlite") throw new Error("Only SQLite supported in this build");
-        if (!active.filePath) throw new Error("Select a SQLite file");
-        await invoke("sqlite_open", { connectionId: active.id, filePath: active.filePath });
-        const s = await invoke<TABLE_TYPE.DbSchemaSummary>("sqlite_schema_summary", { connectionId: active.id });
+        const s = await loadSchemaSummary(active);
         if (!mounted) return;
         setSchema(s);
         setNodes([]);
@@ -56,7 +52,7 @@ export default function VisualizerStub() {
     };
     fetchSchema();
     return () => { mounted = false; };
-  }, [active?.id, active?.filePath, active?.driver, setNodes, setEdges]);
+  }, [active?.id, active?.filePath, active?.driver, active?.host, active?.database, setNodes, setEdges]);
 
   if (!active) return <div className="flex-1 flex items-center justify-center text-gray-500">No active connection</div>;
   if (error) return <div className="flex-1 flex items-center justify-center text-red-400">{error}</div>;
@@ -1,19 +1,69 @@
 import { create } from "zustand";
 import { persist, createJSONStorage } from "zustand/middleware";
-import type { Connection, QueryResult, QueryRun } from "./types";
+import type { Connection, QueryResult, QueryRun, TransactionStatus } from "./types";
 import { invoke } from "@tauri-apps/api/core";
 import { WEBSOCKET_EVENTS, TABLE_TYPE } from "shared";
 
-async function runQuerySqlite(params: { connectionId: string; sql: string; page: number; pageSize: number; signal?: AbortSignal; }): Promise<QueryResult> {
-  // Abort handling is client-side only. If aborted, throw like fetch does.
+// Error string the backend returns when a run is stopped via cancel_query
+const QUERY_CANCELLED = "Query cancelled";
+
+// Error string fetch_page returns once the backend has let go of a result
+const RESULT_CLOSED = "Result is no longer open";
+
+async function runQuerySqlite(params: { connectionId: string; sql: string; page: number; pageSize: number; queryId: string; signal?: AbortSignal; }): Promise<QueryResult> {
+  // If aborted before starting, throw like fetch does. Running queries are interrupted via cancel_query.
   if (params.signal?.aborted) throw new DOMException("Aborted", "AbortError");
   const result = await invoke<QueryResult>("run_sqlite_query", {
     connectionId: params.connectionId,
     sql: params.sql,
+    queryId: params.queryId,
+    options: { page: params.page, pageSize: params.pageSize },
+  });
+  return result;
+}
+
+// Next page of an open result; plan details are kept from the run that opened it
+async function fetchPage(previous: QueryResult, params: { connectionId: string; page: number; pageSize: number; queryId: string; }): Promise<QueryResult> {
+  const next = await invoke<QueryResult>("fetch_page", {
+    connectionId: params.connectionId,
+    resultId: previous.resultId,
     page: params.page,
     pageSize: params.pageSize,
+    queryId: params.queryId,
+  });
+  return { ...previous, queryId: next.queryId, rows: next.rows, totalRows: next.totalRows ?? previous.totalRows };
+}
+
+// Opens (or reuses, when the settings are unchanged) the network session of a postgres/mysql connection
+async function openNetwork(active: Connection): Promise<void> {
+  const { host, port, database, user } = active;
+  if (!host) throw new Error("Host is required");
+  if (!user) throw new Error("User is required");
+  if (!database) throw new Error("Database is required");
+  const resolvedPort = port ?? (active.driver === "postgres" ? 5432 : 3306);
+  await invoke("network_open", {
+    connectionId: active.id,
+    config: {
+      driver: active.driver,
+      host,
+      port: resolvedPort,
+      database,
+      user,
+      password: active.password,
+      ssl: active.ssl ?? false,
+    },
   });
-  return result;
+}
+
+// Tables, keys and foreign keys of a connection, opening its session first
+export async function loadSchemaSummary(active: Connection): Promise<TABLE_TYPE.DbSchemaSummary> {
+  if (active.driver === "sqlite") {
+    if (!active.filePath) throw new Error("Select a SQLite file");
+    await invoke("sqlite_open", { connectionId: active.id, filePath: active.filePath, access: active.sqliteAccess });
+    return invoke<TABLE_TYPE.DbSchemaSummary>("sqlite_schema_summary", { connectionId: active.id });
+  }
+  await openNetwork(active);
+  return invoke<TABLE_TYPE.DbSchemaSummary>("network_schema_summary", { connectionId: active.id });
 }
 
 type StoreState = {
@@ -26,6 +76,8 @@ type StoreState = {
   pageSize: number;
   runningJobId: string | null;
   result: QueryResult | null;
+  openResult: { resultId: string; connectionId: string; sql: string } | null; // Cursor behind `result` and the run that opened it
+  transaction: TransactionStatus | null; // Of the active connection, as of the last run
   lastRunId: string | null;
   wsStatus: "disconnected" | "connecting" | "connected";
   deviceId: string;
@@ -38,6 +90,7 @@ type StoreState = {
   setActiveConnection: (id: string | undefined) => void;
   runQuery: (opts?: { silent?: boolean }) => Promise<void>;
   cancelQuery: () => void;
+  endTransaction: (op: "commit" | "rollback") => Promise<void>;
   initWebSocket: () => void;
   sendMessage: (message: any) => void;
 };
@@ -58,6 +111,8 @@ export const useAppStore = create<StoreState>()(persist((set, get) => {
     pageSize: 10,
     runningJobId: null,
     result: null,
+    openResult: null,
+    transaction: null,
     lastRunId: null,
     wsStatus: "disconnected",
     deviceId: crypto.randomUUID(),
@@ -108,16 +163,11 @@ export const useAppStore = create<StoreState>()(persist((set, get) => {
           set({ schema: null });
           return;
         }
-        if (active.driver !== "sqlite") {
-          set({ schema: null });
-          return;
-        }
-        if (!active.filePath) {
+        if (active.driver === "sqlite" && !active.filePath) {
           set({ schema: null });
           return;
         }
-        await invoke("sqlite_open", { connectionId: active.id, filePath: active.filePath });
-        const summary = await invoke<TABLE_TYPE.DbSchemaSummary>("sqlite_schema_summary", { connectionId: active.id });
+        const summary = await loadSchemaSummary(active);
         set({ schema: summary });
       },
     setPage: (p) => set({ page: p }),
@@ -131,9 +181,21 @@ export const useAppStore = create<StoreState>()(persist((set, get) => {
       }),
     removeConnection: (id) =>
       set((st) => ({ connections: st.connections.filter((c) => c.id !== id), activeConnectionId: st.activeConnectionId === id ? undefined : st.activeConnectionId })),
-    setActiveConnection: (id) => set({ activeConnectionId: id }),
+    setActiveConnection: (id) => {
+      set({ activeConnectionId: id, transaction: null });
+      // A session left open with a transaction keeps it; show that again
+      if (id) invoke<TransactionStatus>("transaction_status", { connectionId: id }).then((transaction) => set({ transaction })).catch(() => { /* not open yet */ });
+    },
     cancelQuery: () => {
       abortController?.abort();
+      const { runningJobId } = get();
+      if (runningJobId) invoke("cancel_query", { queryId: runningJobId }).catch(() => { /* already finished */ });
+    },
+    endTransaction: async (op) => {
+      const { activeConnectionId } = get();
+      if (!activeConnectionId) return;
+      const transaction = await invoke<TransactionStatus>(op, { connectionId: activeConnectionId });
+      set({ transaction });
     },
     runQuery: async (opts) => { 
       const silent = opts?.silent ?? false;
@@ -176,30 +238,46 @@ export const useAppStore = create<StoreState>()(persist((set, get) => {
         const active = connections.find((c) => c.id === activeConnectionId);
         if (!active) throw new Error("Active connection not found");
         let result: QueryResult;
+        // Paging reads on from the open result instead of running the query again,
+        // as long as it is still this connection's and this SQL's
+        const previous = get().result;
+        const open = get().openResult;
+        const pageOnly = silent && previous && open?.connectionId === active.id && open.sql === serializedSQL;
+        // Let go of the previous result before the new run: an open SQLite cursor
+        // keeps its tables locked against DROP/ALTER and the file against writers
+        if (!pageOnly && open) {
+          await invoke("close_result", { connectionId: open.connectionId, resultId: open.resultId }).catch(() => { /* connection closed */ });
+          set({ openResult: null });
+        }
         if (active.driver === "sqlite") {
           if (!active.filePath) throw new Error("Select a SQLite file first");
           // Ensure backend knows about this mapping
-          await invoke("sqlite_open", { connectionId: active.id, filePath: active.filePath });
-          result = await runQuerySqlite({ connectionId: active.id, sql: serializedSQL, page, pageSize, signal: abortController.signal });
+          await invoke("sqlite_open", { connectionId: active.id, filePath: active.filePath, access: active.sqliteAccess });
+          result = pageOnly
+            ? await fetchPage(previous, { connectionId: active.id, page, pageSize, queryId: runId }).catch((err) => {
+                if (err !== RESULT_CLOSED) throw err;
+                return runQuerySqlite({ connectionId: active.id, sql: serializedSQL, page, pageSize, queryId: runId, signal: abortController?.signal });
+              })
+            : await runQuerySqlite({ connectionId: active.id, sql: serializedSQL, page, pageSize, queryId: runId, signal: abortController.signal });
         } else {
           // Network drivers (postgres, mysql)
-          const { host, port, database, user } = active;
-          if (!host) throw new Error("Host is required");
-          if (!user) throw new Error("User is required");
-          if (!database) throw new Error("Database is required");
-          const resolvedPort = port ?? (active.driver === "postgres" ? 5432 : 3306);
-          result = await invoke<QueryResult>("run_network_query", {
-            driver: active.driver,
-            host,
-            port: resolvedPort,
-            database,
-            user,
-            password: active.password,
-            ssl: active.ssl ?? false,
+          await openNetwork(active);
+          const runNetwork = () => invoke<QueryResult>("run_network_query", {
+            connectionId: active.id,
             sql: serializedSQL,
+            queryId: runId,
+            options: { page, pageSize },
           });
+          result = pageOnly
+            ? await fetchPage(previous, { connectionId: active.id, page, pageSize, queryId: runId }).catch((err) => {
+                if (err !== RESULT_CLOSED) throw err;
+                return runNetwork();
+              })
+            : await runNetwork();
         }
         const durationMs = Date.now() - startedAt;
+        // Scripts may open or end a transaction; keep the pending-changes flag current
+        const transaction = await invoke<TransactionStatus>("transaction_status", { connectionId: active.id }).catch(() => null);
         set((st) => {
           let nextHistory = st.history;
           if (silent) {
@@ -213,13 +291,15 @@ export const useAppStore = create<StoreState>()(persist((set, get) => {
           }
           return {
             result,
+            openResult: result.resultId ? { resultId: result.resultId, connectionId: active.id, sql: serializedSQL } : null,
+            transaction,
             runningJobId: undefined,
             history: nextHistory,
           };
         });
       } catch (err) {
         const durationMs = Date.now() - startedAt;
-        const isAbort = err instanceof DOMException && err.name === "AbortError";
+        const isAbort = (err instanceof DOMException && err.name === "AbortError") || err === QUERY_CANCELLED;
         set((st) => {
           let nextHistory = st.history;
           if (silent) {
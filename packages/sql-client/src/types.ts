@@ -1,3 +1,5 @@
+export type SqliteAccess = "readOnly" | "readWrite" | "readWriteCreate";
+
 export type Connection = {
   id: string;
   name: string;
@@ -8,6 +10,7 @@ export type Connection = {
   user?: string;
   password?: string;
   filePath?: string;
+  sqliteAccess?: SqliteAccess; // SQLite only; read-only when unset
   ssl?: boolean;
   // Optional SSH tunnel configuration (for non-SQLite drivers)
   sshEnabled?: boolean;
@@ -32,14 +35,84 @@ export type QueryRun = {
 
 export type TableRow = Record<string, unknown>;
 
+export type ColumnInfo = {
+  name: string;
+  declaredType?: string | null; // type as written in the table definition
+  nativeType?: string | null;   // SQLite affinity, Postgres type name or MySQL column type
+  nullable?: boolean | null;
+  sourceTable?: string | null;
+  sourceColumn?: string | null;
+};
+
 export type QueryResult = {
+  queryId?: string;     // Id accepted by cancel_query while the run is in flight
   columns: string[];
-  rows: TableRow[];
+  rows: TableRow[];     // Shaped per rowEncoding; objects unless the run asked for "arrays" or "columns"
+  rowEncoding?: "objects" | "arrays" | "columns";
+  columnInfo?: ColumnInfo[]; // Type and origin of each entry in columns
   totalRows?: number;
   planSteps?: string[]; // EXPLAIN QUERY PLAN details (if available)
   insights?: string[];  // High-level insights derived from plan
-  planTables?: { table: string; access: "SCAN" | "SEARCH" | "UNKNOWN"; totalRows?: number | null }[];
+  planTables?: PlanTableInfo[];
   rowsScannedEstimate?: number; // Sum of full-scan table row counts when available
+  rowsScannedExact?: boolean | null; // False when built from estimated table sizes
+  plan?: PlanNode | null; // Plan tree from the driver's analyzer
+  indexSuggestions?: IndexSuggestion[] | null; // SQLite only
+  resultSets?: StatementResult[]; // Every statement of a multi-statement run, in order
+  resultId?: string | null; // Open cursor for fetch_page/count_result/close_result; unset for scripts
+};
+
+export type PlanTableInfo = {
+  table: string;
+  access: "SCAN" | "SEARCH" | "UNKNOWN";
+  totalRows?: number | null;
+  totalRowsExact?: boolean; // Counted, rather than estimated from planner statistics
+  accessType?: string | null; // MySQL: ALL, index, range, ref, eq_ref, const, ...
+  key?: string | null;        // MySQL: index used
+  rowsExamined?: number | null;
+  filteredPct?: number | null;
+};
+
+export type IndexSuggestion = {
+  table: string;
+  columns: string[];
+  statement: string; // CREATE INDEX ...
+  reason: string;
+  validated?: boolean | null;        // Whether the plan used the index on a copy of the schema; unset if not checked
+  planWithIndex?: string[] | null;   // EXPLAIN QUERY PLAN details on that copy
+};
+
+export type PlanNode = {
+  operation: string;      // e.g. "Seq Scan", "Hash Join"
+  detail?: string | null; // Readable one-line description, as listed in planSteps
+  table?: string | null;
+  index?: string | null;
+  startupCost?: number | null;
+  totalCost?: number | null;
+  estimatedRows?: number | null;
+  actualRows?: number | null;   // EXPLAIN ANALYZE only; per loop
+  actualTimeMs?: number | null; // EXPLAIN ANALYZE only; per loop
+  loops?: number | null;
+  rowsRemoved?: number | null;  // Rows the node's filter discarded, per loop
+  blocksHit?: number | null;
+  blocksRead?: number | null;
+  flags: string[];              // e.g. "COVERING INDEX", "CORRELATED", "TEMP B-TREE"
+  children: PlanNode[];
+};
+
+export type TransactionStatus = {
+  active: boolean;
+  pendingChanges: boolean; // Something a rollback would discard
+  savepoints: string[];    // Innermost last
+};
+
+export type StatementResult = {
+  columns: string[];
+  columnInfo: ColumnInfo[];
+  rows: TableRow[];
+  rowCount: number;
+  affectedRows?: number | null; // Rows changed; null for statements that only read
+  elapsedMs: number;
 };
 
 export type RunQueryParams = {